@@ -0,0 +1,152 @@
+use serde_json::value::Value;
+
+// Finds the part of the schema describing a setting, given as a dotted path such as
+// network.ethernets.eth0.dhcp4 or as a JSON pointer such as /network/ethernets/eth0/dhcp4.
+//
+// Every patternProperties in the schema matches any name (".*$"), so interface names are
+// resolved through the first pattern instead of being matched against it.
+pub fn lookup<'a>(schema: &'a Value, path: &str) -> Option<&'a Value> {
+    let segments: Vec<String> = match path.strip_prefix('/') {
+        Some(pointer) => pointer
+            .split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+        None => path
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect(),
+    };
+
+    let mut node = resolve(schema, schema);
+    for segment in &segments {
+        node = if let Some(items) = node.get("items") {
+            // Sequences are addressed by index, e.g. routes.0.via
+            if segment.parse::<usize>().is_err() {
+                return None;
+            }
+            items
+        } else if let Some(property) = node.get("properties").and_then(|p| p.get(segment)) {
+            property
+        } else if let Some(Value::Object(patterns)) = node.get("patternProperties") {
+            patterns.values().next()?
+        } else {
+            return None;
+        };
+        node = resolve(schema, node);
+    }
+
+    Some(node)
+}
+
+// Follows $ref to the definition with the matching $id.
+fn resolve<'a>(schema: &'a Value, node: &'a Value) -> &'a Value {
+    let reference = match node.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference,
+        None => return node,
+    };
+
+    let definitions = match schema.get("$defs").and_then(Value::as_object) {
+        Some(definitions) => definitions,
+        None => return node,
+    };
+
+    definitions
+        .values()
+        .find(|d| d.get("$id").and_then(Value::as_str) == Some(reference))
+        .unwrap_or(node)
+}
+
+// Human-readable summary of a schema node.
+pub fn describe(node: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    match node.get("type") {
+        Some(Value::String(t)) => lines.push(format!("type: {t}")),
+        Some(Value::Array(types)) => {
+            let types: Vec<String> = types.iter().map(plain).collect();
+            lines.push(format!("type: {}", types.join(" or ")));
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Array(options)) = node.get("enum") {
+        let options: Vec<String> = options.iter().map(plain).collect();
+        lines.push(format!("one of: {}", options.join(", ")));
+    }
+
+    for keyword in ["minimum", "maximum", "maxLength", "pattern", "format"] {
+        if let Some(value) = node.get(keyword) {
+            lines.push(format!("{keyword}: {}", plain(value)));
+        }
+    }
+
+    if node.get("uniqueItems") == Some(&Value::Bool(true)) {
+        lines.push("items must be unique".to_string());
+    }
+
+    if let Some(items) = node.get("items") {
+        for line in describe(items) {
+            lines.push(format!("items {line}"));
+        }
+    }
+
+    if let Some(Value::Object(properties)) = node.get("properties") {
+        if !properties.is_empty() {
+            let names: Vec<&str> = properties.keys().map(|s| s.as_str()).collect();
+            lines.push(format!("properties: {}", names.join(", ")));
+        }
+    }
+
+    if node.get("patternProperties").is_some() {
+        lines.push("accepts named entries, e.g. interface names".to_string());
+    }
+
+    lines
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::schema;
+
+    #[test]
+    fn test_lookup() {
+        let schema = schema::schema_value();
+
+        let node = lookup(schema, "network.ethernets.eth0.embedded-switch-mode").unwrap();
+        assert_eq!(
+            describe(node),
+            vec!["type: string", "one of: switchdev, legacy"]
+        );
+
+        let node = lookup(schema, "/network/version").unwrap();
+        assert_eq!(
+            describe(node),
+            vec!["type: integer", "minimum: 2", "maximum: 2"]
+        );
+
+        // Through a $ref
+        let node = lookup(schema, "network.renderer").unwrap();
+        assert_eq!(
+            describe(node),
+            vec!["type: string", "one of: networkd, NetworkManager, sriov"]
+        );
+
+        // Through a list
+        let node = lookup(schema, "network.ethernets.eth0.routes.0.scope").unwrap();
+        assert_eq!(
+            describe(node),
+            vec!["type: string", "one of: global, link, host"]
+        );
+
+        assert!(lookup(schema, "network.ethernets.eth0.lynk").is_none());
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Netplan reads its configuration from three directories. A file in a later directory
+// shadows a file with the same name in an earlier one, then every remaining file is
+// applied in the lexical order of its name, no matter which directory it lives in.
+pub const DIRECTORIES: &[&str] = &["lib/netplan", "etc/netplan", "run/netplan"];
+
+// Finds the files netplan would read under root, in the order it would apply them.
+pub fn discover(root: &Path) -> io::Result<Vec<PathBuf>> {
+    // Fail early on a root that doesn't exist, instead of finding nothing in it.
+    fs::metadata(root)?;
+
+    let mut files: BTreeMap<OsString, PathBuf> = BTreeMap::new();
+
+    for directory in DIRECTORIES {
+        let entries = match fs::read_dir(root.join(directory)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "yaml") && !path.is_dir() {
+                files.insert(path.file_name().unwrap().to_owned(), path);
+            }
+        }
+    }
+
+    Ok(files.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover() {
+        let root = std::env::temp_dir().join(format!("netplan-hierarchy-{}", std::process::id()));
+        for directory in DIRECTORIES {
+            fs::create_dir_all(root.join(directory)).unwrap();
+        }
+
+        for file in [
+            "lib/netplan/10-lib.yaml",
+            "lib/netplan/50-shadowed.yaml",
+            "etc/netplan/50-shadowed.yaml",
+            "etc/netplan/90-etc.yaml",
+            "etc/netplan/README",
+            "run/netplan/00-run.yaml",
+            "run/netplan/50-shadowed.yaml",
+        ] {
+            fs::write(root.join(file), "network: {}").unwrap();
+        }
+
+        let files = discover(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let files: Vec<PathBuf> = files
+            .iter()
+            .map(|f| f.strip_prefix(&root).unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("run/netplan/00-run.yaml"),
+                PathBuf::from("lib/netplan/10-lib.yaml"),
+                PathBuf::from("run/netplan/50-shadowed.yaml"),
+                PathBuf::from("etc/netplan/90-etc.yaml"),
+            ]
+        );
+
+        assert!(discover(&root).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Where the YAML to check comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    // How the input is called in the output, stdin_filename names the standard input.
+    pub fn name(&self, stdin_filename: Option<&str>) -> String {
+        match self {
+            Input::Stdin => stdin_filename.unwrap_or("<stdin>").to_string(),
+            Input::File(path) => path.display().to_string(),
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Input::Stdin => io::read_to_string(io::stdin()),
+            Input::File(path) => fs::read_to_string(path),
+        }
+    }
+}
+
+// Turns a command line argument into the inputs it stands for:
+//
+// - `-` is the standard input,
+// - a directory stands for every *.yaml and *.yml file in it, recursively,
+// - a glob pattern such as `etc/**/*.yaml` is expanded without relying on the shell,
+// - anything else is a file, which is reported later if it can't be read.
+//
+// Files are sorted by path so the output doesn't depend on the file system.
+pub fn expand(arg: &str) -> io::Result<Vec<Input>> {
+    if arg == "-" {
+        return Ok(vec![Input::Stdin]);
+    }
+
+    let path = Path::new(arg);
+    if path.is_dir() {
+        let mut files = Vec::new();
+        walk(path, &mut files)?;
+        files.sort();
+        return Ok(files.into_iter().map(Input::File).collect());
+    }
+
+    // A file whose name happens to contain a glob character is taken as is.
+    if path.exists() || !arg.contains(['*', '?', '[']) {
+        return Ok(vec![Input::File(path.to_owned())]);
+    }
+
+    let paths = glob::glob(arg).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid pattern: {err}"),
+        )
+    })?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        // The message of a GlobError includes the path it failed on.
+        let path = path.map_err(|err| io::Error::new(err.error().kind(), err.to_string()))?;
+        if path.is_dir() {
+            walk(&path, &mut files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No files match the pattern",
+        ));
+    }
+    files.sort();
+    files.dedup();
+    Ok(files.into_iter().map(Input::File).collect())
+}
+
+fn walk(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Symbolic links to directories aren't followed, they could form a loop.
+        if entry.file_type()?.is_dir() {
+            walk(&path, files)?;
+        } else if is_yaml(&path) && path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml")
+}
+
+// The standard input can only be read once, so `-` can't be given more than once.
+pub fn repeats_stdin(args: &[String]) -> bool {
+    args.iter().filter(|arg| *arg == "-").count() > 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let root = std::env::temp_dir().join(format!("netplan-inputs-{}", std::process::id()));
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        for file in ["a/01.yaml", "a/b/02.yml", "a/README", "c.yaml"] {
+            fs::write(root.join(file), "network: {}").unwrap();
+        }
+
+        let relative = |inputs: io::Result<Vec<Input>>| -> Vec<PathBuf> {
+            inputs
+                .unwrap()
+                .into_iter()
+                .map(|input| match input {
+                    Input::File(path) => path.strip_prefix(&root).unwrap().to_owned(),
+                    Input::Stdin => PathBuf::from("-"),
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            relative(expand(root.to_str().unwrap())),
+            vec![
+                PathBuf::from("a/01.yaml"),
+                PathBuf::from("a/b/02.yml"),
+                PathBuf::from("c.yaml"),
+            ]
+        );
+        assert_eq!(
+            relative(expand(&format!("{}/*.yaml", root.display()))),
+            vec![PathBuf::from("c.yaml")]
+        );
+        assert_eq!(
+            relative(expand(&format!("{}/**/0*", root.display()))),
+            vec![PathBuf::from("a/01.yaml"), PathBuf::from("a/b/02.yml")]
+        );
+        assert!(expand(&format!("{}/*.json", root.display())).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+
+        // Missing files are left for the caller to report.
+        let missing = root.join("missing.yaml");
+        assert_eq!(
+            expand(missing.to_str().unwrap()).unwrap(),
+            vec![Input::File(missing)]
+        );
+        assert_eq!(expand("-").unwrap(), vec![Input::Stdin]);
+    }
+
+    #[test]
+    fn test_repeats_stdin() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+        assert!(!repeats_stdin(&args(&["a.yaml", "b.yaml"])));
+        assert!(!repeats_stdin(&args(&["-", "a.yaml"])));
+        assert!(repeats_stdin(&args(&["-", "a.yaml", "-"])));
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Input::Stdin.name(None), "<stdin>");
+        assert_eq!(Input::Stdin.name(Some("01-netcfg.yaml")), "01-netcfg.yaml");
+        assert_eq!(
+            Input::File(PathBuf::from("a/01.yaml")).name(Some("01-netcfg.yaml")),
+            "a/01.yaml"
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! Validation of netplan configuration against a JSON schema, along with what is built on top
+//! of it: lints, netplan's merging of several files and descriptions of the settings.
+
+pub mod explain;
+pub mod hierarchy;
+pub mod lint;
+pub mod merge;
+pub mod model;
+pub mod validate;
+mod validator;
+
+pub use lint::Warning;
+pub use validate::{Error, ErrorKind};
+pub use validator::Validator;
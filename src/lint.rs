@@ -0,0 +1,302 @@
+use serde_json::value::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::validate::{error::pointer_push, formats::parse_cidr, Origin, SourceMap, Span};
+
+// Lints catch configuration that is valid according to the schema but most likely not what
+// the user wants. They are reported as warnings and never make a file invalid on their own.
+
+// Device types that define interfaces, in the order netplan documents them.
+pub const DEVICE_TYPES: &[&str] = &[
+    "ethernets",
+    "modems",
+    "wifis",
+    "bridges",
+    "bonds",
+    "tunnels",
+    "vlans",
+    "vrfs",
+    "nm-devices",
+];
+
+// Every lint rule and what it checks, the ids are used in machine-readable output.
+pub const RULES: &[(&str, &str)] = &[
+    ("deprecated-gateway", "gateway4 and gateway6 are deprecated"),
+    ("missing-version", "network.version isn't set"),
+    (
+        "undefined-interface",
+        "Reference to an interface that isn't defined in the same file, or in the merged \
+         configuration with --root",
+    ),
+    (
+        "overlapping-allowed-ips",
+        "WireGuard peers of a tunnel with overlapping allowed IPs",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// One of the ids in RULES.
+    pub rule: &'static str,
+    pub message: String,
+    /// JSON pointer to the node the warning is about.
+    pub instance_path: String,
+    pub span: Option<Span>,
+    /// Files that set the node, when linting a merged configuration.
+    pub origins: Vec<Origin>,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub fn lint(yaml: &str) -> Vec<Warning> {
+    // Files that can't be parsed are already reported by validate().
+    let data = match serde_yaml::from_str::<Value>(yaml) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut warnings = lint_value(&data);
+
+    let source = SourceMap::new(yaml);
+    for warning in &mut warnings {
+        warning.span = source.value(&warning.instance_path);
+    }
+
+    warnings
+}
+
+// Lints data that didn't come straight from a YAML document, the warnings don't have a span.
+pub fn lint_value(data: &Value) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut warn = |rule, message, instance_path| {
+        warnings.push(Warning {
+            rule,
+            message,
+            instance_path,
+            span: None,
+            origins: Vec::new(),
+        })
+    };
+
+    let network = match data.get("network") {
+        Some(network) => network,
+        None => return Vec::new(),
+    };
+
+    if network.get("version").is_none() {
+        warn(
+            "missing-version",
+            "Missing keyword /network/version, it should be set to 2".to_string(),
+            "/network".to_string(),
+        );
+    }
+
+    let mut defined = HashSet::new();
+    for device_type in DEVICE_TYPES {
+        if let Some(Value::Object(devices)) = network.get(device_type) {
+            defined.extend(devices.keys().filter(|k| *k != "renderer"));
+        }
+    }
+
+    for device_type in DEVICE_TYPES {
+        let devices = match network.get(device_type) {
+            Some(Value::Object(devices)) => devices,
+            _ => continue,
+        };
+
+        for (name, device) in devices {
+            let pointer = pointer_push(&pointer_push("/network", device_type), name);
+
+            for gateway in ["gateway4", "gateway6"] {
+                if device.get(gateway).is_some() {
+                    let pointer = pointer_push(&pointer, gateway);
+                    warn(
+                        "deprecated-gateway",
+                        format!(
+                            "Deprecated keyword {pointer}, use a route with `to: default` instead"
+                        ),
+                        pointer,
+                    );
+                }
+            }
+
+            if let Some(Value::String(link)) = device.get("link") {
+                if !defined.contains(link) {
+                    let pointer = pointer_push(&pointer, "link");
+                    warn(
+                        "undefined-interface",
+                        format!("Undefined interface {pointer}: \"{link}\""),
+                        pointer,
+                    );
+                }
+            }
+
+            if let Some(Value::Array(interfaces)) = device.get("interfaces") {
+                for (i, interface) in interfaces.iter().enumerate() {
+                    let interface = match interface.as_str() {
+                        Some(interface) => interface,
+                        None => continue,
+                    };
+                    if !defined.contains(&interface.to_string()) {
+                        let pointer = format!("{pointer}/interfaces/{i}");
+                        warn(
+                            "undefined-interface",
+                            format!("Undefined interface {pointer}: \"{interface}\""),
+                            pointer,
+                        );
+                    }
+                }
+            }
+
+            // WireGuard sends the traffic for an address to a single peer, when the allowed
+            // IPs of two peers overlap only one of them gets it.
+            if let Some(Value::Array(peers)) = device.get("peers") {
+                let mut claimed: Vec<(usize, &str, (IpAddr, u8))> = Vec::new();
+
+                for (i, peer) in peers.iter().enumerate() {
+                    let allowed_ips = match peer.get("allowed-ips") {
+                        Some(Value::Array(allowed_ips)) => allowed_ips,
+                        _ => continue,
+                    };
+
+                    let mut networks = Vec::new();
+                    for (j, allowed_ip) in allowed_ips.iter().enumerate() {
+                        let (text, network) = match allowed_ip.as_str().map(|t| (t, parse_cidr(t)))
+                        {
+                            Some((text, Some(network))) => (text, network),
+                            _ => continue,
+                        };
+
+                        let other = claimed.iter().find(|(_, _, n)| overlap(*n, network));
+                        if let Some((peer, other, _)) = other {
+                            let pointer = format!("{pointer}/peers/{i}/allowed-ips/{j}");
+                            warn(
+                                "overlapping-allowed-ips",
+                                format!(
+                                    "Overlapping allowed IPs {pointer}: \"{text}\", peer {peer} already has \"{other}\""
+                                ),
+                                pointer,
+                            );
+                        }
+                        networks.push((i, text, network));
+                    }
+                    claimed.extend(networks);
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+// Two networks overlap when the shorter prefix contains the address of the other one.
+fn overlap((a, a_prefix): (IpAddr, u8), (b, b_prefix): (IpAddr, u8)) -> bool {
+    let (a, b, bits) = match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => (u32::from(a) as u128, u32::from(b) as u128, 32),
+        (IpAddr::V6(a), IpAddr::V6(b)) => (u128::from(a), u128::from(b), 128),
+        _ => return false,
+    };
+
+    let prefix = a_prefix.min(b_prefix) as u32;
+    let mask = u128::MAX.checked_shl(bits - prefix).unwrap_or(0);
+    a & mask == b & mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_clean() {
+        let warnings = lint(
+            r#"
+            network:
+              version: 2
+              ethernets:
+                eth0: {}
+              vlans:
+                vlan200:
+                  id: 200
+                  link: eth0
+              bridges:
+                br0:
+                  interfaces: [eth0, vlan200]
+        "#,
+        );
+        assert_eq!(warnings, Vec::new());
+    }
+
+    #[test]
+    fn test_lint_warnings() {
+        let warnings = lint(
+            r#"network:
+  ethernets:
+    eth0:
+      gateway4: 10.0.0.1
+  vlans:
+    vlan200:
+      link: eth1
+  bridges:
+    br0:
+      interfaces: [eth0, eth2]
+"#,
+        );
+        let messages: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Missing keyword /network/version, it should be set to 2",
+                "Deprecated keyword /network/ethernets/eth0/gateway4, use a route with `to: default` instead",
+                "Undefined interface /network/bridges/br0/interfaces/1: \"eth2\"",
+                "Undefined interface /network/vlans/vlan200/link: \"eth1\"",
+            ]
+        );
+        assert_eq!(warnings[1].rule, "deprecated-gateway");
+        assert_eq!(warnings[1].span.unwrap().start.line, 4);
+    }
+
+    #[test]
+    fn test_lint_overlapping_allowed_ips() {
+        let warnings = lint(
+            r#"network:
+  version: 2
+  tunnels:
+    wg0:
+      mode: wireguard
+      peers:
+        - allowed-ips: [10.0.0.0/16, "2001:db8::/64"]
+        - allowed-ips: [10.1.0.0/16, 192.168.0.0/24, "2001:db8::1/128"]
+        - allowed-ips: [10.0.42.0/24, 10.1.0.0/16, 172.16.0.0/12]
+"#,
+        );
+        let messages: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Overlapping allowed IPs /network/tunnels/wg0/peers/1/allowed-ips/2: \"2001:db8::1/128\", peer 0 already has \"2001:db8::/64\"",
+                "Overlapping allowed IPs /network/tunnels/wg0/peers/2/allowed-ips/0: \"10.0.42.0/24\", peer 0 already has \"10.0.0.0/16\"",
+                "Overlapping allowed IPs /network/tunnels/wg0/peers/2/allowed-ips/1: \"10.1.0.0/16\", peer 1 already has \"10.1.0.0/16\"",
+            ]
+        );
+        assert_eq!(warnings[0].rule, "overlapping-allowed-ips");
+        assert_eq!(warnings[0].span.unwrap().start.line, 8);
+
+        // 0.0.0.0/0 overlaps everything, IPv4 and IPv6 networks never overlap.
+        assert!(overlap(
+            parse_cidr("0.0.0.0/0").unwrap(),
+            parse_cidr("10.0.0.1/32").unwrap()
+        ));
+        assert!(!overlap(
+            parse_cidr("0.0.0.0/0").unwrap(),
+            parse_cidr("::/0").unwrap()
+        ));
+    }
+}
@@ -1,33 +1,558 @@
-use std::env;
-use std::fs;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use serde_json::value::Value;
+use std::collections::HashSet;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
 
-mod validate;
+use validation_schema::merge::Merger;
+use validation_schema::{explain, hierarchy, lint, validate, Validator};
+
+// The command line front end of the library. What only makes sense for a command stays
+// here: expanding arguments into inputs, checking them concurrently, printing the reports
+// and choosing the exit code.
+mod inputs;
+mod output;
+mod parallel;
+
+use inputs::Input;
+use output::{FileReport, Format, TextOptions};
 
 // WARNING: It doesn't support all the propoerties support by Netplan.
 
-fn main() {
-    let schema = validate::schema::build_schema().unwrap();
+// Exit codes, see the README.
+const EXIT_INVALID: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_IO: u8 = 3;
+
+#[derive(Parser)]
+#[command(version, about = "Validate netplan YAML files")]
+struct Cli {
+    /// Don't print progress messages, only errors and warnings
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print the kind and schema path of every error
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// When to use colors in the text output
+    #[arg(long, global = true, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value_t = Format::Text)]
+    output: Format,
+
+    /// Name to show in messages for the YAML read from the standard input with `-`
+    #[arg(long, global = true, value_name = "NAME")]
+    stdin_filename: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Validate netplan YAML files against the schema
+    Validate {
+        #[arg(required_unless_present = "root")]
+        files: Vec<String>,
+
+        /// Also validate the netplan configuration under this directory, as netplan would
+        /// combine it from {lib,etc,run}/netplan
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Number of files to validate concurrently, 0 for one per CPU
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+    },
+    /// Validate netplan YAML files and warn about valid but questionable settings
+    Lint {
+        #[arg(required_unless_present = "root")]
+        files: Vec<String>,
+
+        /// Also lint the netplan configuration under this directory, as netplan would
+        /// combine it from {lib,etc,run}/netplan
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Treat warnings as errors when computing the exit code
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// Number of files to lint concurrently, 0 for one per CPU
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+    },
+    /// Print the configuration netplan would apply after merging the files in order
+    Merge {
+        #[arg(required_unless_present = "root")]
+        files: Vec<String>,
+
+        /// Merge the netplan configuration under this directory first, as netplan would
+        /// combine it from {lib,etc,run}/netplan
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Instead of the configuration, print which files set the node at this JSON
+        /// pointer and which of them were overridden
+        #[arg(long, value_name = "POINTER")]
+        why: Option<String>,
+    },
+    /// Print the JSON schema used for validation
+    Schema {
+        /// Print the schema as YAML instead of JSON
+        #[arg(long)]
+        yaml: bool,
+    },
+    /// Describe what the schema allows for a setting
+    Explain {
+        /// Dotted path or JSON pointer, e.g. network.ethernets.eth0.dhcp4
+        path: String,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    if let Command::Validate { files, .. }
+    | Command::Lint { files, .. }
+    | Command::Merge { files, .. } = &cli.command
+    {
+        if inputs::repeats_stdin(files) {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "`-` can only be given once, the standard input can only be read once",
+                )
+                .exit();
+        }
+    }
+
+    let text_options = TextOptions {
+        quiet: cli.quiet,
+        verbose: cli.verbose,
+        color: match cli.color {
+            Color::Always => true,
+            Color::Never => false,
+            Color::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        },
+    };
+
+    match &cli.command {
+        Command::Validate { files, root, jobs } => {
+            let mut run = Run::new(false, false, cli.output, text_options);
+            run.stdin_filename = cli.stdin_filename.clone();
+            run.jobs = *jobs;
+            run.check_files(files, root.as_deref());
+            run.finish()
+        }
+        Command::Lint {
+            files,
+            root,
+            deny_warnings,
+            jobs,
+        } => {
+            let mut run = Run::new(true, *deny_warnings, cli.output, text_options);
+            run.stdin_filename = cli.stdin_filename.clone();
+            run.jobs = *jobs;
+            run.check_files(files, root.as_deref());
+            run.finish()
+        }
+        Command::Merge { files, root, why } => {
+            let stdin_filename = cli.stdin_filename.as_deref();
+            print_merged(
+                files,
+                root.as_deref(),
+                why.as_deref(),
+                stdin_filename,
+                cli.output,
+            )
+        }
+        Command::Schema { yaml } => {
+            let schema = validate::schema::schema_value();
+            if *yaml {
+                print!("{}", serde_yaml::to_string(schema).unwrap());
+            } else {
+                println!("{}", serde_json::to_string_pretty(schema).unwrap());
+            }
+            ExitCode::SUCCESS
+        }
+        Command::Explain { path } => {
+            let schema = validate::schema::schema_value();
+            let node = match explain::lookup(schema, path) {
+                Some(node) => node,
+                None => {
+                    eprintln!("{path} isn't a known netplan setting");
+                    return ExitCode::from(EXIT_USAGE);
+                }
+            };
+
+            if cli.output == Format::Text {
+                println!("{path}");
+                for line in explain::describe(node) {
+                    println!("  {line}");
+                }
+            } else {
+                println!("{}", serde_json::to_string_pretty(node).unwrap());
+            }
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+// Prints the combined configuration as YAML, or as JSON with the machine-readable formats.
+fn print_merged(
+    files: &[String],
+    root: Option<&Path>,
+    why: Option<&str>,
+    stdin_filename: Option<&str>,
+    format: Format,
+) -> ExitCode {
+    let mut all_inputs = Vec::new();
+    if let Some(root) = root {
+        match hierarchy::discover(root) {
+            Ok(paths) => all_inputs.extend(paths.into_iter().map(Input::File)),
+            Err(err) => {
+                eprintln!("{}: Failed to read the directory: {err}", root.display());
+                return ExitCode::from(EXIT_IO);
+            }
+        }
+    }
+    for file in files {
+        match inputs::expand(file) {
+            Ok(expanded) => all_inputs.extend(expanded),
+            Err(err) => {
+                eprintln!("{file}: {err}");
+                return ExitCode::from(EXIT_IO);
+            }
+        }
+    }
+
+    let mut merger = Merger::new();
+    for input in &all_inputs {
+        let name = input.name(stdin_filename);
+        let yaml = match input.read() {
+            Ok(yaml) => yaml,
+            Err(err) => {
+                eprintln!("{name}: Failed to read the file: {err}");
+                return ExitCode::from(EXIT_IO);
+            }
+        };
+        if let Err(err) = merger.add(&name, &yaml) {
+            eprintln!("{name}: {}", validate::Error::parse_error(&err));
+            return ExitCode::from(EXIT_INVALID);
+        }
+    }
+
+    let Some(pointer) = why else {
+        if format == Format::Text {
+            print!("{}", serde_yaml::to_string(merger.value()).unwrap());
+        } else {
+            println!("{}", serde_json::to_string_pretty(merger.value()).unwrap());
+        }
+        return ExitCode::SUCCESS;
+    };
+
+    let contributions = merger.why(pointer);
+    if contributions.is_empty() {
+        eprintln!("{pointer} isn't set by any file");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if format == Format::Text {
+        println!("{pointer}");
+        for contribution in contributions {
+            let origin = &contribution.origin;
+            let position = match origin.span {
+                Some(span) => format!("{}:{}:{}", origin.file, span.start.line, span.start.column),
+                None => origin.file.clone(),
+            };
+            let status = if contribution.overridden {
+                "overridden"
+            } else {
+                "set"
+            };
+            println!("  {position}: {status}");
+        }
+    } else {
+        let contributions: Vec<Value> = contributions
+            .iter()
+            .map(|c| {
+                let (line, column) = output::line_column(c.origin.span);
+                serde_json::json!({
+                    "file": c.origin.file,
+                    "line": line,
+                    "column": column,
+                    "overridden": c.overridden,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&contributions).unwrap());
+    }
+    ExitCode::SUCCESS
+}
+
+// State of a validate or lint command across all the files it checks.
+struct Run {
+    validator: &'static Validator,
+    lint: bool,
+    deny_warnings: bool,
+    format: Format,
+    text_options: TextOptions,
+    // Shown instead of <stdin> for `-`.
+    stdin_filename: Option<String>,
+    // Number of files checked concurrently, 0 for one per CPU.
+    jobs: usize,
+    // Only kept for the formats printed once everything has been checked.
+    reports: Vec<FileReport>,
+    valid: usize,
+    invalid: usize,
+    unreadable: usize,
+    // The outcome of the configuration merged from --root, which isn't counted as a file.
+    merged: Option<Outcome>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Valid,
+    Invalid,
+    Unreadable,
+}
+
+impl Run {
+    fn new(lint: bool, deny_warnings: bool, format: Format, text_options: TextOptions) -> Run {
+        Run {
+            validator: Validator::global(),
+            lint,
+            deny_warnings,
+            format,
+            text_options,
+            stdin_filename: None,
+            jobs: 1,
+            reports: Vec::new(),
+            valid: 0,
+            invalid: 0,
+            unreadable: 0,
+            merged: None,
+        }
+    }
+
+    fn check_files(&mut self, files: &[String], root: Option<&Path>) {
+        // Arguments that can't be expanded are reported in their place among the files.
+        let mut pending: Vec<Result<(String, Input), FileReport>> = Vec::new();
+        for file in files {
+            match inputs::expand(file) {
+                Ok(expanded) => {
+                    for input in expanded {
+                        pending.push(Ok((input.name(self.stdin_filename.as_deref()), input)));
+                    }
+                }
+                Err(err) => pending.push(Err(FileReport::unreadable(file, err))),
+            }
+        }
 
-    let args: Vec<String> = env::args().skip(1).collect();
+        let jobs = match self.jobs {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            jobs => jobs,
+        };
+        let (validator, lint) = (self.validator, self.lint);
+        parallel::for_each_ordered(
+            pending,
+            jobs,
+            |file| match file {
+                Ok((name, input)) => check_file(validator, &name, &input, lint).0,
+                Err(report) => report,
+            },
+            |report| self.add(report),
+        );
 
-    if args.len() == 0 {
-        println!("Try passing a bunch of netplan yamls as parameters");
-        return;
+        if let Some(root) = root {
+            self.check_root(root);
+        }
     }
 
-    for file in &args {
-        println!("Parsing {file}");
-        let yaml = fs::read_to_string(file).expect("Failed to open file");
+    // Checks every file netplan would read under root, then the configuration they add up to.
+    fn check_root(&mut self, root: &Path) {
+        let name = root.display().to_string();
 
-        let result = validate::validate(&schema, &yaml);
-        match result {
-            Err(error) => {
-                println!("Validation failed for file {file}");
-                println!("Error: {error}");
+        let paths = match hierarchy::discover(root) {
+            Ok(paths) => paths,
+            Err(err) => {
+                self.add(FileReport::unreadable(&name, err).into_merged());
+                return;
             }
-            Ok(_) => {
-                println!("File {file} is valid");
+        };
+
+        if paths.is_empty() {
+            eprintln!("No netplan configuration found under {}", root.display());
+            return;
+        }
+
+        let mut merger = Merger::new();
+        // The errors of every file, by file, pointer and kind.
+        let mut reported = HashSet::new();
+        for path in paths {
+            // A single file is usually incomplete on its own, e.g. it may refer to interfaces
+            // defined in other files, so only the merged configuration is linted.
+            let file = path.display().to_string();
+            let (report, yaml) = check_file(self.validator, &file, &Input::File(path), false);
+            if let Err(errors) = &report.result {
+                for error in errors {
+                    reported.insert((file.clone(), error.instance_path.clone(), error.kind.name()));
+                }
             }
+            self.add(report);
+
+            // Files that can't be parsed are reported above and left out of the merge.
+            if let Some(yaml) = yaml {
+                let _ = merger.add(&file, &yaml);
+            }
+        }
+
+        // The merged configuration has no source of its own, its errors and warnings point
+        // to the files that set the offending nodes instead.
+        let mut result = self.validator.validate_value(merger.value());
+        if let Err(errors) = &mut result {
+            for error in errors.iter_mut() {
+                error.origins = merger.origins(&error.instance_path);
+            }
+
+            // Most errors of a file are still there once it is merged, only report the ones
+            // that none of the files it comes from already reported. The merged configuration
+            // stays invalid even when all of them were.
+            errors.retain(|error| {
+                !error.origins.iter().any(|origin| {
+                    let key = (
+                        origin.file.clone(),
+                        error.instance_path.clone(),
+                        error.kind.name(),
+                    );
+                    reported.contains(&key)
+                })
+            });
         }
+
+        let mut warnings = if self.lint {
+            lint::lint_value(merger.value())
+        } else {
+            Vec::new()
+        };
+        for warning in &mut warnings {
+            warning.origins = merger.origins(&warning.instance_path);
+        }
+
+        self.add(FileReport::new(&name, result, warnings).into_merged());
+    }
+
+    fn add(&mut self, report: FileReport) {
+        let outcome = if report.is_read_error() {
+            Outcome::Unreadable
+        } else if report.result.is_err() || (self.deny_warnings && !report.warnings.is_empty()) {
+            Outcome::Invalid
+        } else {
+            Outcome::Valid
+        };
+
+        if report.merged {
+            self.merged = Some(outcome);
+        } else {
+            match outcome {
+                Outcome::Valid => self.valid += 1,
+                Outcome::Invalid => self.invalid += 1,
+                Outcome::Unreadable => self.unreadable += 1,
+            }
+        }
+
+        match self.format {
+            Format::Text => output::print_text(&report, &self.text_options),
+            Format::Jsonl => println!("{}", output::file_report(&report)),
+            Format::Json | Format::Sarif => self.reports.push(report),
+        }
+    }
+
+    // e.g. "12 files, 10 valid, 2 invalid", followed by ", merged configuration invalid"
+    // with --root.
+    fn summary(&self) -> String {
+        let total = self.valid + self.invalid + self.unreadable;
+        let mut summary = format!(
+            "{total} file{}, {} valid, {} invalid",
+            if total == 1 { "" } else { "s" },
+            self.valid,
+            self.invalid
+        );
+        if self.unreadable > 0 {
+            summary.push_str(&format!(", {} unreadable", self.unreadable));
+        }
+        if let Some(merged) = self.merged {
+            let outcome = match merged {
+                Outcome::Valid => "valid",
+                Outcome::Invalid => "invalid",
+                Outcome::Unreadable => "unreadable",
+            };
+            summary.push_str(&format!(", merged configuration {outcome}"));
+        }
+        summary
     }
+
+    fn finish(self) -> ExitCode {
+        match self.format {
+            Format::Json => {
+                let reports: Vec<_> = self.reports.iter().map(output::file_report).collect();
+                println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+            }
+            Format::Sarif => {
+                let sarif = output::sarif_report(&self.reports);
+                println!("{}", serde_json::to_string_pretty(&sarif).unwrap());
+            }
+            Format::Text => {
+                if !self.text_options.quiet {
+                    println!("{}", self.summary());
+                }
+            }
+            Format::Jsonl => {}
+        }
+
+        // Not being able to read a file takes precedence, the results are incomplete.
+        if self.unreadable > 0 || self.merged == Some(Outcome::Unreadable) {
+            ExitCode::from(EXIT_IO)
+        } else if self.invalid > 0 || self.merged == Some(Outcome::Invalid) {
+            ExitCode::from(EXIT_INVALID)
+        } else {
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+// Also returns the content of the file, if it could be read.
+fn check_file(
+    validator: &Validator,
+    name: &str,
+    input: &Input,
+    lint: bool,
+) -> (FileReport, Option<String>) {
+    let yaml = match input.read() {
+        Ok(yaml) => yaml,
+        Err(err) => return (FileReport::unreadable(name, err), None),
+    };
+
+    let report = FileReport::new(
+        name,
+        validator.validate_str(&yaml),
+        if lint { lint::lint(&yaml) } else { Vec::new() },
+    );
+
+    (report, Some(yaml))
 }
@@ -0,0 +1,411 @@
+use serde_json::value::Value;
+use std::collections::BTreeMap;
+
+use crate::validate::{error::pointer_push, Origin, SourceMap};
+
+// Netplan merges its configuration files key by key:
+//
+// - mappings are merged recursively,
+// - sequences, such as addresses or nameservers, are concatenated, leaving out the items
+//   that are already there so declaring the same address in two files isn't an error,
+// - anything else set by a later file overrides what earlier files set.
+//
+// Along the way, every node of the merged configuration remembers which files set it, so
+// errors found in the merged configuration can be traced back to the files.
+pub struct Merger {
+    merged: Value,
+    contributions: BTreeMap<String, Vec<Contribution>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+    pub origin: Origin,
+    /// Whether a later file replaced what this one set.
+    pub overridden: bool,
+}
+
+// The file being merged, used to find where each of its nodes is.
+struct Source<'a> {
+    file: &'a str,
+    map: SourceMap,
+}
+
+impl Source<'_> {
+    fn origin(&self, pointer: &str) -> Origin {
+        Origin {
+            file: self.file.to_string(),
+            span: self.map.value(pointer),
+        }
+    }
+}
+
+impl Merger {
+    pub fn new() -> Merger {
+        Merger {
+            merged: Value::Object(Default::default()),
+            contributions: BTreeMap::new(),
+        }
+    }
+
+    // Applies a file on top of the files added before it.
+    pub fn add(&mut self, file: &str, yaml: &str) -> Result<(), serde_yaml::Error> {
+        let data: Value = serde_yaml::from_str(yaml)?;
+        // An empty file doesn't change anything.
+        if data.is_null() {
+            return Ok(());
+        }
+
+        let source = Source {
+            file,
+            map: SourceMap::new(yaml),
+        };
+        let mut merged = std::mem::take(&mut self.merged);
+        self.merge(&mut merged, data, "", "", &source);
+        self.merged = merged;
+        Ok(())
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.merged
+    }
+
+    // Every file that set the node at pointer, in the order they were merged.
+    pub fn why(&self, pointer: &str) -> &[Contribution] {
+        self.contributions
+            .get(pointer)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // Where the node at pointer comes from, or its closest ancestor if the node itself
+    // doesn't exist, e.g. for a missing keyword.
+    pub fn origins(&self, pointer: &str) -> Vec<Origin> {
+        let mut pointer = pointer;
+        loop {
+            let origins: Vec<Origin> = self
+                .why(pointer)
+                .iter()
+                .filter(|c| !c.overridden)
+                .map(|c| c.origin.clone())
+                .collect();
+            if !origins.is_empty() {
+                return origins;
+            }
+            match pointer.rfind('/') {
+                Some(i) => pointer = &pointer[..i],
+                None => return Vec::new(),
+            }
+        }
+    }
+
+    // pointer is the location of base in the merged configuration, file_pointer the
+    // location of other in the file, they differ once sequences are concatenated.
+    fn merge(
+        &mut self,
+        base: &mut Value,
+        other: Value,
+        pointer: &str,
+        file_pointer: &str,
+        source: &Source,
+    ) {
+        match (base, other) {
+            (Value::Object(base), Value::Object(other)) => {
+                self.contribute(pointer, source.origin(file_pointer));
+                for (key, value) in other {
+                    let pointer = pointer_push(pointer, &key);
+                    let file_pointer = pointer_push(file_pointer, &key);
+                    match base.get_mut(&key) {
+                        Some(existing) => {
+                            self.merge(existing, value, &pointer, &file_pointer, source)
+                        }
+                        None => {
+                            self.record(&value, &pointer, &file_pointer, source);
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (Value::Array(base), Value::Array(other)) => {
+                self.contribute(pointer, source.origin(file_pointer));
+                for (i, item) in other.into_iter().enumerate() {
+                    let file_pointer = format!("{file_pointer}/{i}");
+                    match base.iter().position(|existing| *existing == item) {
+                        Some(index) => {
+                            let pointer = format!("{pointer}/{index}");
+                            self.contribute(&pointer, source.origin(&file_pointer));
+                        }
+                        None => {
+                            let pointer = format!("{pointer}/{}", base.len());
+                            self.record(&item, &pointer, &file_pointer, source);
+                            base.push(item);
+                        }
+                    }
+                }
+            }
+            (base, other) => {
+                let prefix = format!("{pointer}/");
+                for (p, contributions) in self.contributions.iter_mut() {
+                    if p == pointer || p.starts_with(&prefix) {
+                        for contribution in contributions {
+                            contribution.overridden = true;
+                        }
+                    }
+                }
+                self.record(&other, pointer, file_pointer, source);
+                *base = other;
+            }
+        }
+    }
+
+    // Records a node that is new to the merged configuration, along with everything in it.
+    fn record(&mut self, value: &Value, pointer: &str, file_pointer: &str, source: &Source) {
+        self.contribute(pointer, source.origin(file_pointer));
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    self.record(
+                        value,
+                        &pointer_push(pointer, key),
+                        &pointer_push(file_pointer, key),
+                        source,
+                    );
+                }
+            }
+            Value::Array(array) => {
+                for (i, item) in array.iter().enumerate() {
+                    self.record(
+                        item,
+                        &format!("{pointer}/{i}"),
+                        &format!("{file_pointer}/{i}"),
+                        source,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn contribute(&mut self, pointer: &str, origin: Origin) {
+        self.contributions
+            .entry(pointer.to_string())
+            .or_default()
+            .push(Contribution {
+                origin,
+                overridden: false,
+            });
+    }
+}
+
+impl Default for Merger {
+    fn default() -> Self {
+        Merger::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn merge_all(files: &[(&str, &str)]) -> Merger {
+        let mut merger = Merger::new();
+        for (file, yaml) in files {
+            merger.add(file, yaml).unwrap();
+        }
+        merger
+    }
+
+    // file:line of each contribution, followed by a * when it was overridden.
+    fn why(merger: &Merger, pointer: &str) -> Vec<String> {
+        merger
+            .why(pointer)
+            .iter()
+            .map(|c| {
+                let line = c.origin.span.unwrap().start.line;
+                let mark = if c.overridden { "*" } else { "" };
+                format!("{}:{line}{mark}", c.origin.file)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_mappings() {
+        let merger = merge_all(&[
+            (
+                "a.yaml",
+                "network:\n  version: 2\n  ethernets:\n    eth0: {dhcp4: true, mtu: 1500}\n",
+            ),
+            (
+                "b.yaml",
+                "network:\n  ethernets:\n    eth0: {dhcp4: false}\n    eth1: {dhcp6: true}\n",
+            ),
+        ]);
+
+        assert_eq!(
+            merger.value(),
+            &json!({
+                "network": {
+                    "version": 2,
+                    "ethernets": {
+                        "eth0": {"dhcp4": false, "mtu": 1500},
+                        "eth1": {"dhcp6": true},
+                    },
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_sequences() {
+        let merger = merge_all(&[
+            (
+                "a.yaml",
+                r#"network:
+  ethernets:
+    eth0:
+      addresses: [10.0.0.1/24, "10.0.0.2/24": {label: a}]
+      nameservers: {addresses: [8.8.8.8], search: [home.com]}
+"#,
+            ),
+            (
+                "b.yaml",
+                r#"network:
+  ethernets:
+    eth0:
+      addresses: [10.0.0.3/24, 10.0.0.1/24]
+      nameservers: {addresses: [8.8.4.4]}
+"#,
+            ),
+        ]);
+
+        // 10.0.0.1/24 is declared by both files but only kept once.
+        assert_eq!(
+            merger.value(),
+            &json!({
+                "network": {"ethernets": {"eth0": {
+                    "addresses": ["10.0.0.1/24", {"10.0.0.2/24": {"label": "a"}}, "10.0.0.3/24"],
+                    "nameservers": {"addresses": ["8.8.8.8", "8.8.4.4"], "search": ["home.com"]},
+                }}}
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_readme_example() {
+        let merger = merge_all(&[
+            (
+                "tests/merge_base.yaml",
+                include_str!("../tests/merge_base.yaml"),
+            ),
+            (
+                "tests/merge_override.yaml",
+                include_str!("../tests/merge_override.yaml"),
+            ),
+        ]);
+
+        assert_eq!(
+            merger.value(),
+            &json!({
+                "network": {
+                    "version": 2,
+                    "ethernets": {"eth0": {
+                        "dhcp4": false,
+                        "addresses": ["10.0.0.2/24", "10.0.0.3/24"],
+                        "nameservers": {"addresses": ["8.8.8.8", "8.8.4.4"]},
+                    }},
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_different_types() {
+        // A later file changing the type of a setting replaces it completely, an empty file
+        // changes nothing.
+        let merger = merge_all(&[
+            (
+                "a.yaml",
+                "network: {ethernets: {eth0: {addresses: [10.0.0.1/24]}}}",
+            ),
+            (
+                "b.yaml",
+                "network: {ethernets: {eth0: {addresses: 10.0.0.2/24}}}",
+            ),
+            ("c.yaml", ""),
+            ("d.yaml", "network: {ethernets: {eth1: {}}}"),
+        ]);
+
+        assert_eq!(
+            merger.value(),
+            &json!({"network": {"ethernets": {
+                "eth0": {"addresses": "10.0.0.2/24"},
+                "eth1": {},
+            }}})
+        );
+    }
+
+    #[test]
+    fn test_provenance() {
+        let merger = merge_all(&[
+            (
+                "a.yaml",
+                r#"network:
+  ethernets:
+    eth0:
+      dhcp4: true
+      addresses: [10.0.0.1/24]
+      routes:
+        - to: default
+          via: 10.0.0.254
+"#,
+            ),
+            (
+                "b.yaml",
+                r#"network:
+  ethernets:
+    eth0:
+      dhcp4: false
+      addresses:
+        - 10.0.0.2/24
+        - 10.0.0.1/24
+      routes: null
+"#,
+            ),
+        ]);
+
+        assert_eq!(
+            why(&merger, "/network/ethernets/eth0"),
+            ["a.yaml:4", "b.yaml:4"]
+        );
+        assert_eq!(
+            why(&merger, "/network/ethernets/eth0/dhcp4"),
+            ["a.yaml:4*", "b.yaml:4"]
+        );
+
+        // Items of a concatenated sequence keep their own position.
+        assert_eq!(
+            why(&merger, "/network/ethernets/eth0/addresses/0"),
+            ["a.yaml:5", "b.yaml:7"]
+        );
+        assert_eq!(
+            why(&merger, "/network/ethernets/eth0/addresses/1"),
+            ["b.yaml:6"]
+        );
+
+        // Whatever was in a replaced node is overridden too.
+        assert_eq!(
+            why(&merger, "/network/ethernets/eth0/routes/0/via"),
+            ["a.yaml:8*"]
+        );
+        assert!(why(&merger, "/network/ethernets/eth1").is_empty());
+
+        let origins = merger.origins("/network/ethernets/eth0/routes/0/via");
+        assert_eq!(origins.len(), 1);
+        assert_eq!(origins[0].file, "b.yaml");
+        assert_eq!(origins[0].span.unwrap().start.line, 8);
+
+        // A node that doesn't exist falls back to its parent.
+        let origins = merger.origins("/network/ethernets/eth0/mtu");
+        assert_eq!(origins.len(), 2);
+    }
+}
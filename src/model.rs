@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod builder;
+pub mod common;
+pub mod devices;
+
+pub use builder::*;
+pub use common::*;
+pub use devices::*;
+
+// Typed netplan configuration, mirroring the schema in validate/schema.rs.
+//
+// The model is meant to read configuration that already passed validation and to build new
+// configuration: keys it doesn't know are ignored instead of rejected, validate a document
+// first to catch them. Optional settings are None when they aren't set and are left out
+// when serializing, so a configuration round-trips the keys the model knows.
+
+// A whole netplan YAML document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    pub network: Network,
+}
+
+impl NetworkConfig {
+    pub fn from_yaml(yaml: &str) -> Result<NetworkConfig, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    // The configuration as netplan YAML, keys in the order of the schema and interfaces
+    // sorted by name.
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("the model should serialize")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Network {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renderer: Option<Renderer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ethernets: Option<Devices<Ethernet>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modems: Option<Devices<Modem>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wifis: Option<Devices<Wifi>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bridges: Option<Devices<Bridge>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bonds: Option<Devices<Bond>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tunnels: Option<Devices<Tunnel>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vlans: Option<Devices<Vlan>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vrfs: Option<Devices<Vrf>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nm_devices: Option<Devices<NmDevice>>,
+}
+
+// The devices of one type, by interface name. Next to the interfaces, the mapping may set
+// the default renderer for all of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Devices<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renderer: Option<Renderer>,
+    #[serde(flatten)]
+    pub interfaces: BTreeMap<String, T>,
+}
+
+// Not derived, it would require T: Default.
+impl<T> Default for Devices<T> {
+    fn default() -> Self {
+        Devices {
+            renderer: None,
+            interfaces: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Renderer {
+    #[serde(rename = "networkd")]
+    Networkd,
+    NetworkManager,
+    #[serde(rename = "sriov")]
+    Sriov,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+    use serde_json::value::Value;
+
+    // Reads a document into the model and back, nothing should be lost on the way.
+    fn round_trip(yaml: &str) -> NetworkConfig {
+        let config: NetworkConfig = serde_yaml::from_str(yaml).unwrap();
+
+        let original: Value = serde_yaml::from_str(yaml).unwrap();
+        let serialized = serde_json::to_value(&config).unwrap();
+        assert_eq!(serialized, original);
+        assert!(Validator::global().validate_value(&serialized).is_ok());
+
+        config
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = round_trip(include_str!("../tests/good_ethernet.yaml"));
+        let network = &config.network;
+        assert_eq!(network.version, Some(2));
+        assert_eq!(network.renderer, Some(Renderer::NetworkManager));
+
+        let eth0 = &network.ethernets.as_ref().unwrap().interfaces["eth0"];
+        assert_eq!(eth0.common.dhcp4, Some(true));
+        assert_eq!(eth0.common.link_local, Some(Vec::new()));
+        assert_eq!(
+            eth0.common.addresses.as_ref().unwrap()[1],
+            Address::WithOptions {
+                address: "10.0.0.1/24".to_string(),
+                options: AddressOptions {
+                    lifetime: None,
+                    label: Some("aaaaaaaaaaaaaaa".to_string()),
+                },
+            }
+        );
+        let routes = eth0.common.routes.as_ref().unwrap();
+        assert_eq!(routes[1].table, Some(100));
+
+        let config = round_trip(include_str!("../tests/good_vlan.yaml"));
+        let vlan = &config.network.vlans.as_ref().unwrap().interfaces["vlan200"];
+        assert_eq!(vlan.id, Some(200));
+        assert_eq!(vlan.link.as_deref(), Some("eth0"));
+    }
+
+    #[test]
+    fn test_devices() {
+        let config = round_trip(
+            r#"
+            network:
+              version: 2
+              ethernets:
+                renderer: networkd
+                eth0:
+                  embedded-switch-mode: switchdev
+                  routing-policy:
+                    - {from: 10.0.0.0/24, table: 100}
+              bridges:
+                br0:
+                  interfaces: [eth0]
+                  parameters: {stp: true, priority: 100}
+              wifis:
+                wlan0:
+                  dhcp4: true
+                  access-points:
+                    home:
+                      password: secret
+                      band: 5GHz
+                      auth: {key-management: psk}
+              bonds:
+                bond0:
+                  interfaces: [eth1, eth2]
+                  parameters:
+                    mode: 802.3ad
+                    transmit-hash-policy: layer3+4
+                    mii-monitor-interval: 100
+                    down-delay: 2s
+              tunnels:
+                gre0:
+                  mode: gre
+                  remote: 10.0.0.2
+                  keys: {input: 1234, output: 10.0.0.1}
+                vxlan0:
+                  mode: vxlan
+                  id: 1000
+                  port-range: [4000, 5000]
+                  checksums: [udp, zero-udp6-tx]
+                wg0:
+                  mode: wireguard
+                  key: /etc/wireguard/private.key
+                  peers:
+                    - endpoint: 203.0.113.1:51820
+                      allowed-ips: [10.10.0.0/24]
+                      keys: {public: /etc/wireguard/peer.pub}
+              modems:
+                cdc-wdm1:
+                  apn: internet
+                  auto-config: false
+                  sim-id: "89014103211118510720"
+                  dhcp4: true
+              vrfs:
+                vrf0:
+                  table: 42
+                  interfaces: [eth0]
+                  routes:
+                    - to: default
+                      via: 10.0.0.1
+                      table: 42
+        "#,
+        );
+
+        let network = &config.network;
+        let ethernets = network.ethernets.as_ref().unwrap();
+        assert_eq!(ethernets.renderer, Some(Renderer::Networkd));
+        assert_eq!(
+            ethernets.interfaces["eth0"].embedded_switch_mode,
+            Some(EmbeddedSwitchMode::Switchdev)
+        );
+
+        let bond0 = &network.bonds.as_ref().unwrap().interfaces["bond0"];
+        let parameters = bond0.parameters.as_ref().unwrap();
+        assert_eq!(parameters.mode, Some(BondMode::Ieee8023ad));
+        assert_eq!(
+            parameters.transmit_hash_policy,
+            Some(TransmitHashPolicy::Layer3Plus4)
+        );
+        assert_eq!(parameters.mii_monitor_interval, Some(Interval::Number(100)));
+        assert_eq!(
+            parameters.down_delay,
+            Some(Interval::WithUnit("2s".to_string()))
+        );
+
+        let tunnels = &network.tunnels.as_ref().unwrap().interfaces;
+        assert_eq!(
+            tunnels["gre0"].keys,
+            Some(TunnelKeys::Keys(Keys {
+                input: Some(KeyValue::Number(1234)),
+                output: Some(KeyValue::Text("10.0.0.1".to_string())),
+                private: None,
+            }))
+        );
+        assert_eq!(tunnels["vxlan0"].port_range, Some([4000, 5000]));
+        assert_eq!(tunnels["wg0"].mode, Some(TunnelMode::Wireguard));
+        assert_eq!(
+            tunnels["wg0"].peers.as_ref().unwrap()[0]
+                .endpoint
+                .as_deref(),
+            Some("203.0.113.1:51820")
+        );
+
+        let modem = &network.modems.as_ref().unwrap().interfaces["cdc-wdm1"];
+        assert_eq!(modem.apn.as_deref(), Some("internet"));
+        assert_eq!(modem.common.dhcp4, Some(true));
+
+        let vrf0 = &network.vrfs.as_ref().unwrap().interfaces["vrf0"];
+        assert_eq!(vrf0.table, Some(42));
+        assert_eq!(vrf0.interfaces, Some(vec!["eth0".to_string()]));
+
+        let wlan0 = &network.wifis.as_ref().unwrap().interfaces["wlan0"];
+        let home = &wlan0.access_points.as_ref().unwrap()["home"];
+        assert_eq!(home.band, Some(Band::FiveGhz));
+        assert_eq!(
+            home.auth.as_ref().unwrap().key_management,
+            Some(KeyManagement::Psk)
+        );
+    }
+
+    #[test]
+    fn test_addresses() {
+        let addresses: Vec<Address> = serde_yaml::from_str(
+            r#"
+            - 10.0.0.1/24
+            - 10.0.0.2/24: {lifetime: 0}
+            - 10.0.0.3/24: {lifetime: forever, label: a}
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            addresses,
+            vec![
+                Address::Plain("10.0.0.1/24".to_string()),
+                Address::WithOptions {
+                    address: "10.0.0.2/24".to_string(),
+                    options: AddressOptions {
+                        lifetime: Some(Lifetime::Zero),
+                        label: None,
+                    },
+                },
+                Address::WithOptions {
+                    address: "10.0.0.3/24".to_string(),
+                    options: AddressOptions {
+                        lifetime: Some(Lifetime::Forever),
+                        label: Some("a".to_string()),
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&addresses).unwrap(),
+            serde_json::json!([
+                "10.0.0.1/24",
+                {"10.0.0.2/24": {"lifetime": 0}},
+                {"10.0.0.3/24": {"lifetime": "forever", "label": "a"}},
+            ])
+        );
+        let config = serde_json::json!({
+            "network": {"version": 2, "ethernets": {"eth0": {"addresses": addresses}}}
+        });
+        assert!(Validator::global().validate_value(&config).is_ok());
+
+        // A map holds a single address.
+        assert!(serde_yaml::from_str::<Address>("{10.0.0.1/24: {}, 10.0.0.2/24: {}}").is_err());
+        assert!(serde_yaml::from_str::<Lifetime>("1").is_err());
+    }
+}
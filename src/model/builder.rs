@@ -0,0 +1,696 @@
+use super::*;
+use crate::{Error, Validator};
+
+// Builds a configuration in code instead of writing YAML by hand:
+//
+//   let config = NetworkConfig::builder()
+//       .ethernet("eth0", |e| e.dhcp4(true).address("10.0.0.2/24"))
+//       .vlan("vlan10", |v| v.id(10).link("eth0"))
+//       .build()?;
+//   print!("{}", config.to_yaml());
+//
+// build() validates the result against the schema, so a configuration it returns is
+// always valid. Values the types can't check on their own, such as an address label that
+// is too long, are reported there.
+pub struct NetworkConfigBuilder {
+    network: Network,
+}
+
+impl NetworkConfig {
+    pub fn builder() -> NetworkConfigBuilder {
+        NetworkConfigBuilder {
+            network: Network {
+                version: Some(2),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl NetworkConfigBuilder {
+    pub fn renderer(mut self, renderer: Renderer) -> Self {
+        self.network.renderer = Some(renderer);
+        self
+    }
+
+    pub fn ethernet<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(EthernetBuilder) -> EthernetBuilder,
+    {
+        let ethernet = f(EthernetBuilder(Ethernet::default())).0;
+        add(&mut self.network.ethernets, name, ethernet);
+        self
+    }
+
+    pub fn modem<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(ModemBuilder) -> ModemBuilder,
+    {
+        let modem = f(ModemBuilder(Modem::default())).0;
+        add(&mut self.network.modems, name, modem);
+        self
+    }
+
+    pub fn wifi<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(WifiBuilder) -> WifiBuilder,
+    {
+        let wifi = f(WifiBuilder(Wifi::default())).0;
+        add(&mut self.network.wifis, name, wifi);
+        self
+    }
+
+    pub fn bridge<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(BridgeBuilder) -> BridgeBuilder,
+    {
+        let bridge = f(BridgeBuilder(Bridge::default())).0;
+        add(&mut self.network.bridges, name, bridge);
+        self
+    }
+
+    pub fn bond<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(BondBuilder) -> BondBuilder,
+    {
+        let bond = f(BondBuilder(Bond::default())).0;
+        add(&mut self.network.bonds, name, bond);
+        self
+    }
+
+    pub fn tunnel<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(TunnelBuilder) -> TunnelBuilder,
+    {
+        let tunnel = f(TunnelBuilder(Tunnel::default())).0;
+        add(&mut self.network.tunnels, name, tunnel);
+        self
+    }
+
+    pub fn vlan<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(VlanBuilder) -> VlanBuilder,
+    {
+        let vlan = f(VlanBuilder(Vlan::default())).0;
+        add(&mut self.network.vlans, name, vlan);
+        self
+    }
+
+    pub fn vrf<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(VrfBuilder) -> VrfBuilder,
+    {
+        let vrf = f(VrfBuilder(Vrf::default())).0;
+        add(&mut self.network.vrfs, name, vrf);
+        self
+    }
+
+    pub fn build(self) -> Result<NetworkConfig, Vec<Error>> {
+        let config = NetworkConfig {
+            network: self.network,
+        };
+        let value = serde_json::to_value(&config).expect("the model should serialize");
+        Validator::global().validate_value(&value)?;
+        Ok(config)
+    }
+}
+
+// Adding an interface that already exists replaces it.
+fn add<T>(devices: &mut Option<Devices<T>>, name: &str, device: T) {
+    devices
+        .get_or_insert_with(Devices::default)
+        .interfaces
+        .insert(name.to_string(), device);
+}
+
+pub struct EthernetBuilder(Ethernet);
+pub struct ModemBuilder(Modem);
+pub struct WifiBuilder(Wifi);
+pub struct BridgeBuilder(Bridge);
+pub struct BondBuilder(Bond);
+pub struct TunnelBuilder(Tunnel);
+pub struct VlanBuilder(Vlan);
+pub struct VrfBuilder(Vrf);
+
+// Setters for the settings every type of device has.
+macro_rules! common_setters {
+    ($($builder:ident),*) => {$(
+        impl $builder {
+            pub fn renderer(mut self, renderer: Renderer) -> Self {
+                self.0.common.renderer = Some(renderer);
+                self
+            }
+
+            pub fn dhcp4(mut self, enabled: bool) -> Self {
+                self.0.common.dhcp4 = Some(enabled);
+                self
+            }
+
+            pub fn dhcp6(mut self, enabled: bool) -> Self {
+                self.0.common.dhcp6 = Some(enabled);
+                self
+            }
+
+            pub fn accept_ra(mut self, enabled: bool) -> Self {
+                self.0.common.accept_ra = Some(enabled);
+                self
+            }
+
+            pub fn link_local(mut self, link_local: Vec<LinkLocal>) -> Self {
+                self.0.common.link_local = Some(link_local);
+                self
+            }
+
+            /// An address with its prefix length, e.g. 10.0.0.2/24.
+            pub fn address(mut self, address: &str) -> Self {
+                self.0
+                    .common
+                    .addresses
+                    .get_or_insert_with(Vec::new)
+                    .push(Address::Plain(address.to_string()));
+                self
+            }
+
+            pub fn address_with_options(mut self, address: &str, options: AddressOptions) -> Self {
+                self.0
+                    .common
+                    .addresses
+                    .get_or_insert_with(Vec::new)
+                    .push(Address::WithOptions {
+                        address: address.to_string(),
+                        options,
+                    });
+                self
+            }
+
+            pub fn nameserver(mut self, address: &str) -> Self {
+                self.0
+                    .common
+                    .nameservers
+                    .get_or_insert_with(Nameservers::default)
+                    .addresses
+                    .get_or_insert_with(Vec::new)
+                    .push(address.to_string());
+                self
+            }
+
+            pub fn search_domain(mut self, domain: &str) -> Self {
+                self.0
+                    .common
+                    .nameservers
+                    .get_or_insert_with(Nameservers::default)
+                    .search
+                    .get_or_insert_with(Vec::new)
+                    .push(domain.to_string());
+                self
+            }
+
+            pub fn route(mut self, route: Route) -> Self {
+                self.0.common.routes.get_or_insert_with(Vec::new).push(route);
+                self
+            }
+
+            pub fn routing_policy(mut self, rule: RoutingPolicy) -> Self {
+                self.0
+                    .common
+                    .routing_policy
+                    .get_or_insert_with(Vec::new)
+                    .push(rule);
+                self
+            }
+
+            /// A route to `default`, what gateway4 and gateway6 used to do.
+            pub fn default_route(self, via: &str) -> Self {
+                self.route(Route {
+                    to: Some("default".to_string()),
+                    via: Some(via.to_string()),
+                    ..Default::default()
+                })
+            }
+
+            pub fn macaddress(mut self, macaddress: &str) -> Self {
+                self.0.common.macaddress = Some(macaddress.to_string());
+                self
+            }
+
+            pub fn mtu(mut self, mtu: u32) -> Self {
+                self.0.common.mtu = Some(mtu);
+                self
+            }
+
+            pub fn optional(mut self, optional: bool) -> Self {
+                self.0.common.optional = Some(optional);
+                self
+            }
+
+            /// Sets anything the other setters don't cover.
+            pub fn with<F: FnOnce(&mut CommonProperties)>(mut self, f: F) -> Self {
+                f(&mut self.0.common);
+                self
+            }
+        }
+    )*};
+}
+
+common_setters!(
+    EthernetBuilder,
+    ModemBuilder,
+    WifiBuilder,
+    BridgeBuilder,
+    BondBuilder,
+    TunnelBuilder,
+    VlanBuilder,
+    VrfBuilder
+);
+
+impl EthernetBuilder {
+    pub fn link(mut self, link: &str) -> Self {
+        self.0.link = Some(link.to_string());
+        self
+    }
+
+    pub fn virtual_function_count(mut self, count: u32) -> Self {
+        self.0.virtual_function_count = Some(count);
+        self
+    }
+
+    pub fn embedded_switch_mode(mut self, mode: EmbeddedSwitchMode) -> Self {
+        self.0.embedded_switch_mode = Some(mode);
+        self
+    }
+
+    pub fn match_macaddress(mut self, macaddress: &str) -> Self {
+        self.0
+            .common
+            .match_
+            .get_or_insert_with(Match::default)
+            .macaddress = Some(macaddress.to_string());
+        self
+    }
+}
+
+impl ModemBuilder {
+    pub fn apn(mut self, apn: &str) -> Self {
+        self.0.apn = Some(apn.to_string());
+        self
+    }
+
+    pub fn auto_config(mut self, enabled: bool) -> Self {
+        self.0.auto_config = Some(enabled);
+        self
+    }
+
+    pub fn number(mut self, number: &str) -> Self {
+        self.0.number = Some(number.to_string());
+        self
+    }
+
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.0.username = Some(username.to_string());
+        self.0.password = Some(password.to_string());
+        self
+    }
+
+    pub fn pin(mut self, pin: &str) -> Self {
+        self.0.pin = Some(pin.to_string());
+        self
+    }
+}
+
+impl WifiBuilder {
+    pub fn access_point(mut self, ssid: &str, access_point: AccessPoint) -> Self {
+        self.0
+            .access_points
+            .get_or_insert_with(Default::default)
+            .insert(ssid.to_string(), access_point);
+        self
+    }
+}
+
+impl BridgeBuilder {
+    pub fn interface(mut self, name: &str) -> Self {
+        self.0
+            .interfaces
+            .get_or_insert_with(Vec::new)
+            .push(name.to_string());
+        self
+    }
+
+    pub fn parameters(mut self, parameters: BridgeParameters) -> Self {
+        self.0.parameters = Some(parameters);
+        self
+    }
+}
+
+impl BondBuilder {
+    pub fn interface(mut self, name: &str) -> Self {
+        self.0
+            .interfaces
+            .get_or_insert_with(Vec::new)
+            .push(name.to_string());
+        self
+    }
+
+    pub fn mode(mut self, mode: BondMode) -> Self {
+        self.0
+            .parameters
+            .get_or_insert_with(BondParameters::default)
+            .mode = Some(mode);
+        self
+    }
+
+    pub fn parameters(mut self, parameters: BondParameters) -> Self {
+        self.0.parameters = Some(parameters);
+        self
+    }
+}
+
+impl TunnelBuilder {
+    pub fn mode(mut self, mode: TunnelMode) -> Self {
+        self.0.mode = Some(mode);
+        self
+    }
+
+    pub fn local(mut self, address: &str) -> Self {
+        self.0.local = Some(address.to_string());
+        self
+    }
+
+    pub fn remote(mut self, address: &str) -> Self {
+        self.0.remote = Some(address.to_string());
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.0.port = Some(port);
+        self
+    }
+
+    /// The VXLAN Network Identifier.
+    pub fn id(mut self, id: u32) -> Self {
+        self.0.id = Some(id);
+        self
+    }
+
+    /// The private key of a WireGuard tunnel, base64 encoded or the path to a file.
+    pub fn private_key(mut self, key: &str) -> Self {
+        self.0.key = Some(TunnelKeys::Key(key.to_string()));
+        self
+    }
+
+    pub fn peer(mut self, peer: WireguardPeer) -> Self {
+        self.0.peers.get_or_insert_with(Vec::new).push(peer);
+        self
+    }
+}
+
+impl VlanBuilder {
+    pub fn id(mut self, id: u32) -> Self {
+        self.0.id = Some(id);
+        self
+    }
+
+    pub fn link(mut self, link: &str) -> Self {
+        self.0.link = Some(link.to_string());
+        self
+    }
+}
+
+impl VrfBuilder {
+    pub fn table(mut self, table: u32) -> Self {
+        self.0.table = Some(table);
+        self
+    }
+
+    pub fn interface(mut self, name: &str) -> Self {
+        self.0
+            .interfaces
+            .get_or_insert_with(Vec::new)
+            .push(name.to_string());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn test_builder() {
+        let config = NetworkConfig::builder()
+            .renderer(Renderer::Networkd)
+            .ethernet("eth0", |e| {
+                e.dhcp4(false)
+                    .address("10.0.0.2/24")
+                    .default_route("10.0.0.1")
+                    .nameserver("10.0.0.1")
+                    .search_domain("example.com")
+                    .match_macaddress("a0:4b:cc:1d:ee:0a")
+            })
+            .ethernet("eth1", |e| e.dhcp4(true))
+            .vlan("vlan10", |v| {
+                v.id(10).link("eth0").address_with_options(
+                    "10.10.0.2/24",
+                    AddressOptions {
+                        label: Some("vlan10:0".to_string()),
+                        ..Default::default()
+                    },
+                )
+            })
+            .bridge("br0", |b| b.interface("bond0").dhcp4(true))
+            .bond("bond0", |b| {
+                b.interface("eth1")
+                    .interface("eth2")
+                    .parameters(BondParameters {
+                        primary: Some("eth1".to_string()),
+                        mii_monitor_interval: Some(Interval::Number(100)),
+                        up_delay: Some(Interval::WithUnit("1s".to_string())),
+                        ..Default::default()
+                    })
+                    .mode(BondMode::ActiveBackup)
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            config.to_yaml(),
+            r#"network:
+  version: 2
+  renderer: networkd
+  ethernets:
+    eth0:
+      dhcp4: false
+      addresses:
+      - 10.0.0.2/24
+      nameservers:
+        search:
+        - example.com
+        addresses:
+        - 10.0.0.1
+      routes:
+      - to: default
+        via: 10.0.0.1
+      match:
+        macaddress: a0:4b:cc:1d:ee:0a
+    eth1:
+      dhcp4: true
+  bridges:
+    br0:
+      dhcp4: true
+      interfaces:
+      - bond0
+  bonds:
+    bond0:
+      interfaces:
+      - eth1
+      - eth2
+      parameters:
+        mode: active-backup
+        mii-monitor-interval: 100
+        up-delay: 1s
+        primary: eth1
+  vlans:
+    vlan10:
+      addresses:
+      - 10.10.0.2/24:
+          label: vlan10:0
+      id: 10
+      link: eth0
+"#
+        );
+
+        // What is printed can be read back.
+        assert_eq!(NetworkConfig::from_yaml(&config.to_yaml()).unwrap(), config);
+        assert!(Validator::global().validate_str(&config.to_yaml()).is_ok());
+    }
+
+    #[test]
+    fn test_builder_address_lifetimes() {
+        let config = NetworkConfig::builder().ethernet("eth0", |e| {
+            e.address_with_options(
+                "10.0.0.2/24",
+                AddressOptions {
+                    lifetime: Some(Lifetime::Zero),
+                    ..Default::default()
+                },
+            )
+            .address_with_options(
+                "10.0.0.3/24",
+                AddressOptions {
+                    lifetime: Some(Lifetime::Forever),
+                    ..Default::default()
+                },
+            )
+        });
+        assert!(config.build().is_ok());
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let errors = NetworkConfig::builder()
+            .ethernet("eth0", |e| {
+                e.macaddress("not a mac address").address_with_options(
+                    "10.0.0.2/24",
+                    AddressOptions {
+                        label: Some("a label that is too long".to_string()),
+                        ..Default::default()
+                    },
+                )
+            })
+            .build()
+            .unwrap_err();
+
+        let kinds: Vec<&str> = errors.iter().map(|e| e.kind.name()).collect();
+        assert_eq!(kinds, vec!["too-long", "pattern-mismatch"]);
+        assert!(matches!(errors[0].kind, ErrorKind::TooLong { limit: 15 }));
+    }
+
+    #[test]
+    fn test_builder_tunnels() {
+        let config = NetworkConfig::builder()
+            .tunnel("wg0", |t| {
+                t.mode(TunnelMode::Wireguard)
+                    .port(51820)
+                    .private_key("/etc/wireguard/private.key")
+                    .address("10.10.0.1/24")
+                    .peer(WireguardPeer {
+                        endpoint: Some("203.0.113.1:51820".to_string()),
+                        allowed_ips: Some(vec!["10.10.0.0/24".to_string()]),
+                        ..Default::default()
+                    })
+            })
+            .tunnel("vxlan0", |t| {
+                t.mode(TunnelMode::Vxlan).id(1000).remote("239.0.0.1")
+            })
+            .build()
+            .unwrap();
+        assert!(Validator::global().validate_str(&config.to_yaml()).is_ok());
+
+        // Peers only make sense for WireGuard, a tunnel needs a mode.
+        let errors = NetworkConfig::builder()
+            .tunnel("gre0", |t| {
+                t.mode(TunnelMode::Gre)
+                    .remote("10.0.0.2")
+                    .peer(WireguardPeer::default())
+            })
+            .tunnel("tun0", |t| t.remote("10.0.0.3"))
+            .build()
+            .unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unexpected keyword /network/tunnels/gre0/peers",
+                "Missing keyword /network/tunnels/tun0/mode",
+            ]
+        );
+
+        // Values the schema doesn't allow are reported by build(), e.g. a port of 0.
+        let errors = NetworkConfig::builder()
+            .tunnel("wg0", |t| t.mode(TunnelMode::Wireguard).port(0))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors[0].to_string(),
+            "Unexpected value /network/tunnels/wg0/port: 0, must be between 1 and 65535"
+        );
+    }
+
+    #[test]
+    fn test_builder_vrfs() {
+        let config = NetworkConfig::builder()
+            .ethernet("eth0", |e| e.dhcp4(true))
+            .vrf("vrf0", |v| {
+                v.table(42)
+                    .interface("eth0")
+                    .route(Route {
+                        to: Some("10.1.0.0/16".to_string()),
+                        via: Some("10.0.0.1".to_string()),
+                        table: Some(42),
+                        ..Default::default()
+                    })
+                    .routing_policy(RoutingPolicy {
+                        from: Some("10.0.0.0/24".to_string()),
+                        table: Some(42),
+                        ..Default::default()
+                    })
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.network.vrfs.unwrap().interfaces["vrf0"].table,
+            Some(42)
+        );
+
+        // A VRF needs a table, and its routes can't use another one.
+        let errors = NetworkConfig::builder()
+            .vrf("vrf0", |v| v.interface("eth0"))
+            .vrf("vrf1", |v| {
+                v.table(42).route(Route {
+                    to: Some("default".to_string()),
+                    table: Some(100),
+                    ..Default::default()
+                })
+            })
+            .build()
+            .unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Missing keyword /network/vrfs/vrf0/table",
+                "Unexpected value /network/vrfs/vrf1/routes/0/table: 100, expected 42 as in /network/vrfs/vrf1/table",
+            ]
+        );
+        assert_eq!(errors[1].kind.name(), "mismatched-value");
+    }
+
+    #[test]
+    fn test_builder_modems() {
+        let config = NetworkConfig::builder()
+            .renderer(Renderer::NetworkManager)
+            .modem("cdc-wdm1", |m| {
+                m.apn("internet")
+                    .credentials("user", "secret")
+                    .pin("1234")
+                    .dhcp4(true)
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.to_yaml(),
+            r#"network:
+  version: 2
+  renderer: NetworkManager
+  modems:
+    cdc-wdm1:
+      dhcp4: true
+      apn: internet
+      password: secret
+      pin: '1234'
+      username: user
+"#
+        );
+    }
+}
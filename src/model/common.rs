@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::Renderer;
+
+// Settings shared by every type of device, see COMMON_PROPERTIES in validate/schema.rs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CommonProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renderer: Option<Renderer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dhcp4: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dhcp6: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_mtu: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_privacy: Option<bool>,
+    /// An empty list disables link-local addresses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_local: Option<Vec<LinkLocal>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_carrier: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dhcp_identifier: Option<DhcpIdentifier>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dhcp4_overrides: Option<DhcpOverrides>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dhcp6_overrides: Option<DhcpOverrides>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_ra: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<Address>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_address_generation: Option<Ipv6AddressGeneration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6_address_token: Option<String>,
+    /// Deprecated by netplan, use a route to `default` instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway4: Option<String>,
+    /// Deprecated by netplan, use a route to `default` instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway6: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nameservers: Option<Nameservers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macaddress: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optional: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optional_addresses: Option<Vec<OptionalAddress>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activation_mode: Option<ActivationMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routes: Option<Vec<Route>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_policy: Option<Vec<RoutingPolicy>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub neigh_suppress: Option<bool>,
+    #[serde(rename = "match", skip_serializing_if = "Option::is_none")]
+    pub match_: Option<Match>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkLocal {
+    Ipv4,
+    Ipv6,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DhcpIdentifier {
+    Duid,
+    Mac,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DhcpOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_dns: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_ntp: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_hostname: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_hostname: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_mtu: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_routes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route_metric: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_domains: Option<bool>,
+}
+
+// An address with its prefix length, either on its own or with options:
+//
+//   addresses:
+//     - 10.0.0.1/24
+//     - 10.0.0.2/24:
+//         label: eth0:1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "AddressRepr", into = "AddressRepr")]
+pub enum Address {
+    Plain(String),
+    WithOptions {
+        address: String,
+        options: AddressOptions,
+    },
+}
+
+impl Address {
+    pub fn address(&self) -> &str {
+        match self {
+            Address::Plain(address) | Address::WithOptions { address, .. } => address,
+        }
+    }
+}
+
+// How an address is written in YAML, the map form must have a single entry.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AddressRepr {
+    Plain(String),
+    WithOptions(BTreeMap<String, AddressOptions>),
+}
+
+impl TryFrom<AddressRepr> for Address {
+    type Error = String;
+
+    fn try_from(repr: AddressRepr) -> Result<Self, Self::Error> {
+        match repr {
+            AddressRepr::Plain(address) => Ok(Address::Plain(address)),
+            AddressRepr::WithOptions(map) if map.len() == 1 => {
+                let (address, options) = map.into_iter().next().unwrap();
+                Ok(Address::WithOptions { address, options })
+            }
+            AddressRepr::WithOptions(map) => Err(format!(
+                "expected a single address with its options, found {}",
+                map.len()
+            )),
+        }
+    }
+}
+
+impl From<Address> for AddressRepr {
+    fn from(address: Address) -> Self {
+        match address {
+            Address::Plain(address) => AddressRepr::Plain(address),
+            Address::WithOptions { address, options } => {
+                AddressRepr::WithOptions(BTreeMap::from([(address, options)]))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AddressOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lifetime: Option<Lifetime>,
+    /// At most 15 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+// The schema allows the string `forever` or the number 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "LifetimeRepr", into = "LifetimeRepr")]
+pub enum Lifetime {
+    Forever,
+    Zero,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LifetimeRepr {
+    Word(String),
+    Number(u64),
+}
+
+impl TryFrom<LifetimeRepr> for Lifetime {
+    type Error = String;
+
+    fn try_from(repr: LifetimeRepr) -> Result<Self, Self::Error> {
+        match repr {
+            LifetimeRepr::Word(word) if word == "forever" => Ok(Lifetime::Forever),
+            LifetimeRepr::Number(0) => Ok(Lifetime::Zero),
+            _ => Err("expected forever or 0".to_string()),
+        }
+    }
+}
+
+impl From<Lifetime> for LifetimeRepr {
+    fn from(lifetime: Lifetime) -> Self {
+        match lifetime {
+            Lifetime::Forever => LifetimeRepr::Word("forever".to_string()),
+            Lifetime::Zero => LifetimeRepr::Number(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ipv6AddressGeneration {
+    Eui64,
+    StablePrivacy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Nameservers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OptionalAddress {
+    Ipv4Ll,
+    Ipv6Ra,
+    Dhcp4,
+    Dhcp6,
+    Static,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivationMode {
+    Manual,
+    Off,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Route {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_link: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric: Option<u32>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<RouteType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<RouteScope>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub congestion_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advertised_receive_window: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteType {
+    Unicast,
+    Anycast,
+    Blackhole,
+    Broadcast,
+    Local,
+    Multicast,
+    Nat,
+    Prohibit,
+    Throw,
+    Unreachable,
+    Xresolve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteScope {
+    Global,
+    Link,
+    Host,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RoutingPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_of_service: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macaddress: Option<String>,
+}
@@ -0,0 +1,511 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::CommonProperties;
+
+// One struct per type of device. The settings shared by every type are flattened into each
+// of them, as they are in the YAML.
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Ethernet {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    /// The physical function of an SR-IOV virtual function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub virtual_function_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedded_switch_mode: Option<EmbeddedSwitchMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_virtual_functions_rebind: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub infiniband_mode: Option<InfinibandMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddedSwitchMode {
+    Switchdev,
+    Legacy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InfinibandMode {
+    Datagram,
+    Connected,
+}
+
+// A GSM or CDMA modem, only supported by NetworkManager.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Modem {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apn: Option<String>,
+    /// Let NetworkManager look up the APN, username and password in its database.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_config: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sim_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sim_operator_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Wifi {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    /// Access points by SSID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_points: Option<BTreeMap<String, AccessPoint>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AccessPoint {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<AccessPointMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bssid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub band: Option<Band>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessPointMode {
+    Infrastructure,
+    Ap,
+    Adhoc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Band {
+    #[serde(rename = "5GHz")]
+    FiveGhz,
+    #[serde(rename = "2.4GHz")]
+    TwoPointFourGhz,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Auth {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_management: Option<KeyManagement>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<AuthMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anonymous_identity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_certificate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_certificate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase2_auth: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyManagement {
+    None,
+    Psk,
+    Eap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMethod {
+    Tls,
+    Peap,
+    Ttls,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bridge {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interfaces: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<BridgeParameters>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BridgeParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ageing_time: Option<String>,
+    /// Alternative spelling of ageing-time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aging_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u16>,
+    /// At most 63.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_priority: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_delay: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hello_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_cost: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stp: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bond {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interfaces: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<BondParameters>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BondParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<BondMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lacp_rate: Option<LacpRate>,
+    /// In milliseconds when there is no unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mii_monitor_interval: Option<Interval>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_links: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transmit_hash_policy: Option<TransmitHashPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ad_select: Option<AdSelect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub all_members_active: Option<bool>,
+    /// In milliseconds when there is no unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arp_interval: Option<Interval>,
+    /// At most 16 IPv4 addresses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arp_ip_targets: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arp_validate: Option<ArpValidate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arp_all_targets: Option<ArpAllTargets>,
+    /// In milliseconds when there is no unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_delay: Option<Interval>,
+    /// In milliseconds when there is no unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down_delay: Option<Interval>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_over_mac_policy: Option<FailOverMacPolicy>,
+    /// Between 1 and 255.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gratuitous_arp: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packets_per_member: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_reselect_policy: Option<PrimaryReselectPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resend_igmp: Option<u8>,
+    /// In seconds when there is no unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub learn_packet_interval: Option<Interval>,
+    /// The preferred member interface in active-backup mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<String>,
+}
+
+// A number, in the unit of the setting it is used for, or a number with a time unit such as
+// "2s".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Interval {
+    Number(u64),
+    WithUnit(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BondMode {
+    BalanceRr,
+    ActiveBackup,
+    BalanceXor,
+    Broadcast,
+    #[serde(rename = "802.3ad")]
+    Ieee8023ad,
+    BalanceTlb,
+    BalanceAlb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LacpRate {
+    Slow,
+    Fast,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransmitHashPolicy {
+    #[serde(rename = "layer2")]
+    Layer2,
+    #[serde(rename = "layer3+4")]
+    Layer3Plus4,
+    #[serde(rename = "layer2+3")]
+    Layer2Plus3,
+    #[serde(rename = "encap2+3")]
+    Encap2Plus3,
+    #[serde(rename = "encap3+4")]
+    Encap3Plus4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdSelect {
+    Stable,
+    Bandwidth,
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArpValidate {
+    None,
+    Active,
+    Backup,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArpAllTargets {
+    Any,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailOverMacPolicy {
+    None,
+    Active,
+    Follow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrimaryReselectPolicy {
+    Always,
+    Better,
+    Failure,
+}
+
+// Some settings only apply to some modes, e.g. peers to WireGuard tunnels, see the schema.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Tunnel {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    /// Required by the schema; checked by `build()`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<TunnelMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<TunnelKeys>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<TunnelKeys>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u8>,
+    /// Listening port of a WireGuard tunnel, destination port of a VXLAN one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// The VXLAN Network Identifier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mac_learning: Option<bool>,
+    /// In seconds when there is no unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ageing: Option<Interval>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arp_proxy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<Vec<VxlanNotification>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_circuit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<Vec<VxlanChecksum>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<VxlanExtension>>,
+    /// The lowest and the highest source port.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_range: Option<[u16; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_label: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub do_not_fragment: Option<bool>,
+
+    /// Firewall mark of the outgoing WireGuard packets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peers: Option<Vec<WireguardPeer>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelMode {
+    Sit,
+    Gre,
+    Ip6gre,
+    Ipip,
+    Ipip6,
+    Ip6ip6,
+    Vti,
+    Vti6,
+    Gretap,
+    Ip6gretap,
+    Isatap,
+    Wireguard,
+    Vxlan,
+}
+
+// A single key, a number or an IPv4 address for GRE-like tunnels and the private key for
+// WireGuard ones, or a mapping with the keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TunnelKeys {
+    Number(u32),
+    Key(String),
+    Keys(Keys),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keys {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<KeyValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<KeyValue>,
+    /// WireGuard only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyValue {
+    Number(u32),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VxlanNotification {
+    L2Miss,
+    L3Miss,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VxlanChecksum {
+    Udp,
+    ZeroUdp6Tx,
+    ZeroUdp6Rx,
+    RemoteTx,
+    RemoteRx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VxlanExtension {
+    GroupPolicy,
+    GenericProtocol,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WireguardPeer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_ips: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keepalive: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<PeerKeys>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerKeys {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vlan {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vrf {
+    #[serde(flatten)]
+    pub common: CommonProperties,
+    /// Routing table of the VRF, its routes either leave the table out or use this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interfaces: Option<Vec<String>>,
+}
+
+// Devices only NetworkManager knows about, not described by the schema.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NmDevice {}
@@ -0,0 +1,555 @@
+use serde_json::{json, value::Value};
+use std::io;
+
+use validation_schema::lint::{self, Warning};
+use validation_schema::validate::{Error, Location, Origin, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+    Jsonl,
+    Sarif,
+}
+
+// Everything found in a single file.
+pub struct FileReport {
+    pub file: String,
+    pub result: Result<(), Vec<Error>>,
+    pub warnings: Vec<Warning>,
+    /// Set when the file couldn't be read, nothing else was checked then.
+    pub read_error: Option<io::Error>,
+    /// The configuration merged from --root, file is the root directory then.
+    pub merged: bool,
+}
+
+impl FileReport {
+    pub fn new(file: &str, result: Result<(), Vec<Error>>, warnings: Vec<Warning>) -> Self {
+        FileReport {
+            file: file.to_string(),
+            result,
+            warnings,
+            read_error: None,
+            merged: false,
+        }
+    }
+
+    pub fn unreadable(file: &str, error: io::Error) -> Self {
+        FileReport {
+            file: file.to_string(),
+            result: Ok(()),
+            warnings: Vec::new(),
+            read_error: Some(error),
+            merged: false,
+        }
+    }
+
+    // The report of the configuration merged from a root directory.
+    pub fn into_merged(self) -> Self {
+        FileReport {
+            merged: true,
+            ..self
+        }
+    }
+
+    // How the file is called in the text output.
+    fn name(&self) -> String {
+        if self.merged {
+            format!("{} (merged)", self.file)
+        } else {
+            self.file.clone()
+        }
+    }
+
+    pub fn is_read_error(&self) -> bool {
+        self.read_error.is_some()
+    }
+}
+
+// Reading a file is the only thing that can fail before validating it, these errors are
+// reported with their own "read-error" kind.
+const READ_ERROR: &str = "read-error";
+
+fn read_error_message(error: &io::Error) -> String {
+    format!("Failed to read the file: {error}")
+}
+
+pub struct TextOptions {
+    /// Only print errors and warnings.
+    pub quiet: bool,
+    /// Also print the kind and schema path of every error.
+    pub verbose: bool,
+    /// Use ANSI colors.
+    pub color: bool,
+}
+
+impl TextOptions {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn position(&self, file: &str, location: Option<Location>) -> String {
+        match location {
+            Some(location) => self.paint(
+                "1",
+                &format!("{file}:{}:{}:", location.line, location.column),
+            ),
+            None => self.paint("1", &format!("{file}:")),
+        }
+    }
+}
+
+pub fn print_text(report: &FileReport, options: &TextOptions) {
+    let file = &report.name();
+
+    if !options.quiet {
+        println!("Parsing {file}");
+    }
+
+    if let Some(error) = &report.read_error {
+        eprintln!(
+            "{} {}",
+            options.position(file, None),
+            read_error_message(error)
+        );
+        return;
+    }
+
+    match &report.result {
+        Err(errors) => {
+            println!(
+                "{}",
+                options.paint("31", &format!("Validation failed for file {file}"))
+            );
+            for error in errors {
+                println!("{} {error}", options.position(file, error.location()));
+                print_origins(&error.origins, options);
+                if options.verbose {
+                    println!(
+                        "    kind: {}, schema path: {}",
+                        error.kind.name(),
+                        error.schema_path
+                    );
+                }
+            }
+        }
+        Ok(_) => {
+            if !options.quiet {
+                println!("{}", options.paint("32", &format!("File {file} is valid")));
+            }
+        }
+    }
+
+    for warning in &report.warnings {
+        println!(
+            "{} {} {warning}",
+            options.position(file, warning.span.map(|s| s.start)),
+            options.paint("33", "warning:")
+        );
+        print_origins(&warning.origins, options);
+        if options.verbose {
+            println!("    rule: {}", warning.rule);
+        }
+    }
+}
+
+fn print_origins(origins: &[Origin], options: &TextOptions) {
+    for origin in origins {
+        let position = match origin.span {
+            Some(span) => format!("{}:{}:{}", origin.file, span.start.line, span.start.column),
+            None => origin.file.clone(),
+        };
+        println!("    from {}", options.paint("1", &position));
+    }
+}
+
+// The report of a single file, see the "Machine-readable output" section of the README.
+pub fn file_report(report: &FileReport) -> Value {
+    let errors: Vec<Value> = match (&report.read_error, &report.result) {
+        (Some(error), _) => {
+            let mut error = node_report("", None, &[], read_error_message(error));
+            error["kind"] = json!(READ_ERROR);
+            vec![error]
+        }
+        (None, Ok(_)) => Vec::new(),
+        (None, Err(errors)) => errors
+            .iter()
+            .map(|e| {
+                let mut error = node_report(&e.instance_path, e.span, &e.origins, e.to_string());
+                error["kind"] = json!(e.kind.name());
+                error
+            })
+            .collect(),
+    };
+
+    let warnings: Vec<Value> = report
+        .warnings
+        .iter()
+        .map(|w| {
+            let mut warning = node_report(&w.instance_path, w.span, &w.origins, w.to_string());
+            warning["rule"] = json!(w.rule);
+            warning
+        })
+        .collect();
+
+    json!({
+        "file": report.file,
+        "merged": report.merged,
+        "valid": report.read_error.is_none() && report.result.is_ok(),
+        "errors": errors,
+        "warnings": warnings,
+    })
+}
+
+fn node_report(pointer: &str, span: Option<Span>, origins: &[Origin], message: String) -> Value {
+    let (line, column) = line_column(span);
+    let origins: Vec<Value> = origins
+        .iter()
+        .map(|origin| {
+            let (line, column) = line_column(origin.span);
+            json!({ "file": origin.file, "line": line, "column": column })
+        })
+        .collect();
+
+    json!({
+        "message": message,
+        "path": dotted_path(pointer),
+        "pointer": pointer,
+        "line": line,
+        "column": column,
+        "origins": origins,
+    })
+}
+
+pub fn line_column(span: Option<Span>) -> (Value, Value) {
+    match span {
+        Some(span) => (json!(span.start.line), json!(span.start.column)),
+        None => (Value::Null, Value::Null),
+    }
+}
+
+// One SARIF rule per error kind, the ids are the ones returned by ErrorKind::name().
+const RULES: &[(&str, &str)] = &[
+    ("unexpected-keyword", "Unknown key"),
+    ("missing-keyword", "Required key missing"),
+    ("duplicate-item", "Duplicate item in a list of unique items"),
+    ("invalid-enum", "Value isn't one of the allowed options"),
+    ("out-of-range", "Number out of range"),
+    (
+        "pattern-mismatch",
+        "Value doesn't match the expected pattern",
+    ),
+    ("invalid-format", "Value isn't in the expected format"),
+    ("invalid-type", "Value of the wrong type"),
+    ("too-long", "Value longer than allowed"),
+    (
+        "mismatched-value",
+        "Value doesn't match the one of a related setting",
+    ),
+    ("invalid-value", "Invalid value"),
+    ("parse-error", "Invalid YAML"),
+    (READ_ERROR, "File couldn't be read"),
+];
+
+// A SARIF 2.1.0 log with a single run covering every file. Lint rules follow the error
+// kinds in the list of rules and their results are reported as warnings.
+pub fn sarif_report(reports: &[FileReport]) -> Value {
+    let all_rules: Vec<&(&str, &str)> = RULES.iter().chain(lint::RULES.iter()).collect();
+
+    let rules: Vec<Value> = all_rules
+        .iter()
+        .map(|(id, description)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    let mut push = |report: &FileReport,
+                    rule: &str,
+                    level: &str,
+                    message: String,
+                    span: Option<Span>,
+                    origins: &[Origin]| {
+        let rule_index = all_rules.iter().position(|(id, _)| *id == rule).unwrap();
+
+        let mut result = json!({
+            "ruleId": rule,
+            "ruleIndex": rule_index,
+            "level": level,
+            "message": { "text": message },
+        });
+
+        // Errors in a merged configuration point to the file that set the offending node, or
+        // to the root directory when no file did.
+        let location = match origins.first() {
+            Some(origin) if report.merged => physical_location(&origin.file, origin.span),
+            _ => physical_location(&report.file, span),
+        };
+        result["locations"] = json!([{ "physicalLocation": location }]);
+        if report.merged {
+            result["properties"] = json!({ "merged": true });
+        }
+
+        // The files a merged configuration was made of.
+        if !origins.is_empty() {
+            let related: Vec<Value> = origins
+                .iter()
+                .enumerate()
+                .map(|(i, origin)| {
+                    json!({
+                        "id": i,
+                        "message": { "text": "Set here" },
+                        "physicalLocation": physical_location(&origin.file, origin.span),
+                    })
+                })
+                .collect();
+            result["relatedLocations"] = json!(related);
+        }
+
+        results.push(result);
+    };
+
+    for report in reports {
+        if let Some(error) = &report.read_error {
+            push(
+                report,
+                READ_ERROR,
+                "error",
+                read_error_message(error),
+                None,
+                &[],
+            );
+        }
+
+        if let Err(errors) = &report.result {
+            for error in errors {
+                push(
+                    report,
+                    error.kind.name(),
+                    "error",
+                    error.to_string(),
+                    error.span,
+                    &error.origins,
+                );
+            }
+        }
+
+        for warning in &report.warnings {
+            push(
+                report,
+                warning.rule,
+                "warning",
+                warning.to_string(),
+                warning.span,
+                &warning.origins,
+            );
+        }
+    }
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn physical_location(file: &str, span: Option<Span>) -> Value {
+    let mut physical_location = json!({
+        "artifactLocation": { "uri": file },
+    });
+    if let Some(span) = span {
+        physical_location["region"] = json!({
+            "startLine": span.start.line,
+            "startColumn": span.start.column,
+            "endLine": span.end.line,
+            "endColumn": span.end.column,
+        });
+    }
+    physical_location
+}
+
+// Turns a JSON pointer into the dotted notation used by the netplan documentation,
+// e.g. /network/ethernets/eth0/dhcp4 -> network.ethernets.eth0.dhcp4
+fn dotted_path(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|chunk| chunk.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validation_schema::Validator;
+
+    fn check(file: &str, yaml: &str) -> FileReport {
+        FileReport::new(
+            file,
+            Validator::global().validate_str(yaml),
+            lint::lint(yaml),
+        )
+    }
+
+    #[test]
+    fn test_file_report() {
+        let report = check(
+            "01-netcfg.yaml",
+            r#"network:
+  ethernets:
+    "eth0/1":
+      dhcp4: nothanks
+"#,
+        );
+
+        assert_eq!(
+            file_report(&report),
+            json!({
+                "file": "01-netcfg.yaml",
+                "merged": false,
+                "valid": false,
+                "errors": [{
+                    "kind": "invalid-type",
+                    "message": "Unexpected value /network/ethernets/eth0~11/dhcp4: \"nothanks\", expected a boolean",
+                    "path": "network.ethernets.eth0/1.dhcp4",
+                    "pointer": "/network/ethernets/eth0~11/dhcp4",
+                    "line": 4,
+                    "column": 14,
+                    "origins": [],
+                }],
+                "warnings": [{
+                    "rule": "missing-version",
+                    "message": "Missing keyword /network/version, it should be set to 2",
+                    "path": "network",
+                    "pointer": "/network",
+                    "line": 2,
+                    "column": 3,
+                    "origins": [],
+                }],
+            })
+        );
+
+        let report = check("01-netcfg.yaml", "network:\n  version: 2\n");
+        assert_eq!(
+            file_report(&report),
+            json!({"file": "01-netcfg.yaml", "merged": false, "valid": true, "errors": [], "warnings": []})
+        );
+    }
+
+    #[test]
+    fn test_unreadable_file_report() {
+        let error = io::Error::new(io::ErrorKind::NotFound, "No such file or directory");
+        let report = FileReport::unreadable("missing.yaml", error);
+
+        assert_eq!(
+            file_report(&report),
+            json!({
+                "file": "missing.yaml",
+                "merged": false,
+                "valid": false,
+                "errors": [{
+                    "kind": "read-error",
+                    "message": "Failed to read the file: No such file or directory",
+                    "path": "",
+                    "pointer": "",
+                    "line": null,
+                    "column": null,
+                    "origins": [],
+                }],
+                "warnings": [],
+            })
+        );
+
+        let run = &sarif_report(&[report])["runs"][0];
+        assert_eq!(run["results"][0]["ruleId"], "read-error");
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let reports = vec![
+            check("bad.yaml", "network:\n  renderer: something\n"),
+            check("good.yaml", "network:\n  version: 2\n"),
+        ];
+
+        let sarif = sarif_report(&reports);
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        let rules = &run["tool"]["driver"]["rules"];
+        assert_eq!(
+            rules.as_array().unwrap().len(),
+            RULES.len() + lint::RULES.len()
+        );
+        assert_eq!(run["results"].as_array().unwrap().len(), 2);
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "invalid-enum");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            rules[result["ruleIndex"].as_u64().unwrap() as usize]["id"],
+            "invalid-enum"
+        );
+        assert_eq!(
+            result["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "bad.yaml" },
+                "region": { "startLine": 2, "startColumn": 13, "endLine": 2, "endColumn": 22 },
+            })
+        );
+
+        let result = &run["results"][1];
+        assert_eq!(result["ruleId"], "missing-version");
+        assert_eq!(result["level"], "warning");
+        assert_eq!(
+            rules[result["ruleIndex"].as_u64().unwrap() as usize]["id"],
+            "missing-version"
+        );
+    }
+
+    #[test]
+    fn test_sarif_merged_report() {
+        let origin = Origin {
+            file: "/tmp/root/etc/netplan/01-netcfg.yaml".to_string(),
+            span: Validator::global()
+                .validate_str("network:\n  renderer: something\n")
+                .unwrap_err()[0]
+                .span,
+        };
+        let mut errors = Validator::global()
+            .validate_value(&json!({"network": {"renderer": "something"}}))
+            .unwrap_err();
+        errors[0].origins = vec![origin];
+        let report = FileReport::new("/tmp/root", Err(errors), Vec::new()).into_merged();
+
+        assert_eq!(report.name(), "/tmp/root (merged)");
+        assert_eq!(file_report(&report)["file"], "/tmp/root");
+        assert_eq!(file_report(&report)["merged"], true);
+
+        let result = &sarif_report(&[report])["runs"][0]["results"][0];
+        assert_eq!(
+            result["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "/tmp/root/etc/netplan/01-netcfg.yaml" },
+                "region": { "startLine": 2, "startColumn": 13, "endLine": 2, "endColumn": 22 },
+            })
+        );
+        assert_eq!(result["properties"], json!({ "merged": true }));
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+// Applies f to every item on up to `jobs` threads and hands the results to emit in the
+// order of the items, each one as soon as it and every result before it are available.
+pub fn for_each_ordered<T, R, F, E>(items: Vec<T>, jobs: usize, f: F, mut emit: E)
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+    E: FnMut(R),
+{
+    let jobs = jobs.min(items.len());
+    if jobs <= 1 {
+        items.into_iter().map(f).for_each(emit);
+        return;
+    }
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let (queue, f, sender) = (&queue, &f, sender.clone());
+            scope.spawn(move || loop {
+                // The lock is released before f runs.
+                let next = queue.lock().unwrap().next();
+                let Some((index, item)) = next else {
+                    break;
+                };
+                if sender.send((index, f(item))).is_err() {
+                    break;
+                }
+            });
+        }
+        // Lets the loop below end once every thread is done.
+        drop(sender);
+
+        let mut done = BTreeMap::new();
+        let mut next = 0;
+        for (index, result) in receiver {
+            done.insert(index, result);
+            while let Some(result) = done.remove(&next) {
+                emit(result);
+                next += 1;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_for_each_ordered() {
+        for jobs in [0, 1, 4, 100] {
+            let mut results = Vec::new();
+            for_each_ordered(
+                (0..20u64).collect(),
+                jobs,
+                |i| {
+                    // Later items finish first.
+                    thread::sleep(Duration::from_millis(20 - i));
+                    i * 2
+                },
+                |r| results.push(r),
+            );
+            assert_eq!(results, (0..20).map(|i| i * 2).collect::<Vec<u64>>());
+        }
+    }
+}
@@ -1,58 +1,162 @@
-use jsonschema::{error::ValidationErrorKind, JSONSchema};
+use jsonschema::{
+    error::{TypeKind, ValidationErrorKind},
+    JSONSchema,
+};
 use serde_json::value::Value;
 
+pub mod error;
+pub(crate) mod formats;
+pub mod location;
 pub mod schema;
+mod semantic;
+mod suggest;
 
-pub fn validate(schema: &JSONSchema, yaml: &str) -> Result<(), String> {
+pub use error::{Error, ErrorKind};
+pub use location::{Location, Origin, SourceMap, Span};
+
+pub fn validate(schema: &JSONSchema, yaml: &str) -> Result<(), Vec<Error>> {
     let data = match serde_yaml::from_str::<Value>(yaml) {
         Ok(v) => v,
-        Err(_) => {
-            return Err("serde_yaml failed to parse the file".to_string());
+        Err(err) => {
+            return Err(vec![Error::parse_error(&err)]);
         }
     };
 
-    if let Err(err) = schema.validate(&data) {
+    validate_value(schema, &data).map_err(|mut errors| {
+        let source = SourceMap::new(yaml);
+        for error in &mut errors {
+            error.span = match error.kind {
+                ErrorKind::UnexpectedKeyword { .. } => source.key(&error.instance_path),
+                _ => source.value(&error.instance_path),
+            };
+        }
+        errors
+    })
+}
+
+// Validates data that didn't come straight from a YAML document, such as the result of
+// merging several files. The errors don't have a span.
+pub fn validate_value(schema: &JSONSchema, data: &Value) -> Result<(), Vec<Error>> {
+    let mut errors = Vec::new();
+
+    if let Err(err) = schema.validate(data) {
+        // Used to suggest alternatives to unexpected keywords and to find ranges.
+        let schema_data = schema::schema_value();
+
         for error in err {
-            match error.kind {
+            let instance_path = error.instance_path.to_string();
+            let schema_path = error.schema_path.to_string();
+
+            let kind = match error.kind {
                 ValidationErrorKind::AdditionalProperties { unexpected } => {
-                    for u in &unexpected {
-                        let e =
-                            format!("Unexpected keyword {}/{u}", error.instance_path.to_string());
-                        return Err(e);
+                    for u in unexpected {
+                        let instance = error.instance.get(&u).cloned();
+                        let instance_path = error::pointer_push(&instance_path, &u);
+                        errors.push(Error {
+                            span: None,
+                            origins: Vec::new(),
+                            kind: ErrorKind::UnexpectedKeyword {
+                                suggestions: suggest::suggestions(schema_data, &schema_path, &u),
+                                keyword: u,
+                            },
+                            instance_path,
+                            instance,
+                            schema_path: schema_path.clone(),
+                        });
                     }
+                    continue;
                 }
-                ValidationErrorKind::UniqueItems => {
-                    let e = format!(
-                        "Duplicate item {}/{}",
-                        error.instance_path.to_string(),
-                        error.instance.to_string(),
-                    );
-                    return Err(e);
+                ValidationErrorKind::Required { property } => {
+                    let keyword = property.as_str().unwrap_or_default().to_string();
+                    errors.push(Error {
+                        span: None,
+                        origins: Vec::new(),
+                        instance_path: error::pointer_push(&instance_path, &keyword),
+                        kind: ErrorKind::MissingKeyword { keyword },
+                        instance: None,
+                        schema_path,
+                    });
+                    continue;
                 }
-                _ => {
-                    let e = format!(
-                        "Unexpected value {}: {}",
-                        error.instance_path.to_string(),
-                        error.instance.to_string()
-                    );
-                    return Err(e);
+                // A property that only some variants allow, e.g. peers for a tunnel that
+                // isn't a WireGuard one.
+                ValidationErrorKind::FalseSchema => ErrorKind::UnexpectedKeyword {
+                    keyword: instance_path
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default()
+                        .replace("~1", "/")
+                        .replace("~0", "~"),
+                    suggestions: Vec::new(),
+                },
+                ValidationErrorKind::UniqueItems => ErrorKind::DuplicateItem,
+                ValidationErrorKind::Enum { options } => ErrorKind::InvalidEnum {
+                    options: options.as_array().cloned().unwrap_or_default(),
+                },
+                ValidationErrorKind::Minimum { .. } | ValidationErrorKind::Maximum { .. } => {
+                    let (minimum, maximum) = range(schema_data, &schema_path);
+                    ErrorKind::OutOfRange { minimum, maximum }
                 }
-            }
+                ValidationErrorKind::Pattern { pattern } => ErrorKind::PatternMismatch { pattern },
+                ValidationErrorKind::Format { format } => ErrorKind::InvalidFormat {
+                    format: format.to_string(),
+                },
+                ValidationErrorKind::Type { kind } => ErrorKind::InvalidType {
+                    expected: match kind {
+                        TypeKind::Single(t) => vec![t.to_string()],
+                        TypeKind::Multiple(types) => {
+                            types.into_iter().map(|t| t.to_string()).collect()
+                        }
+                    },
+                },
+                ValidationErrorKind::MaxLength { limit } => ErrorKind::TooLong { limit },
+                _ => ErrorKind::InvalidValue,
+            };
+
+            errors.push(Error {
+                kind,
+                span: None,
+                origins: Vec::new(),
+                instance_path,
+                instance: Some(error.instance.into_owned()),
+                schema_path,
+            });
         }
     }
 
-    Ok(())
+    // Rules the schema can't express, such as values that depend on other nodes.
+    errors.extend(semantic::check(data));
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Both bounds of the range a minimum or maximum keyword belongs to.
+fn range(schema: &Value, schema_path: &str) -> (Option<Value>, Option<Value>) {
+    let parent = match schema_path.rfind('/') {
+        Some(i) => &schema_path[..i],
+        None => return (None, None),
+    };
+
+    match schema.pointer(parent) {
+        Some(s) => (s.get("minimum").cloned(), s.get("maximum").cloned()),
+        None => (None, None),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Validator;
 
     #[test]
     fn test_net_a_network() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             somethingelse:
               version: 2
@@ -63,9 +167,9 @@ mod tests {
 
     #[test]
     fn test_network_invalid_property() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               notavalidproperty: 2
@@ -74,11 +178,301 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_all_errors_are_reported() {
+        let schema = Validator::global().schema();
+        let result = validate(
+            schema,
+            r#"
+            network:
+              version: 3
+              renderer: invalid
+              ethernets:
+                eth0:
+                  weird-property: 1
+                  another-weird-property: 2
+        "#,
+        );
+        let errors = result.err().unwrap();
+        assert_eq!(errors.len(), 4);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert!(messages
+            .contains(&"Unexpected keyword /network/ethernets/eth0/weird-property".to_string()));
+        assert!(messages.contains(
+            &"Unexpected keyword /network/ethernets/eth0/another-weird-property".to_string()
+        ));
+    }
+
+    #[test]
+    fn test_error_details() {
+        let schema = Validator::global().schema();
+        let result = validate(
+            schema,
+            r#"
+            network:
+              vlans:
+                vlan200:
+                  lynk: eth0
+        "#,
+        );
+        let errors = result.err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].kind,
+            ErrorKind::UnexpectedKeyword {
+                keyword: "lynk".to_string(),
+                suggestions: vec!["link".to_string()],
+            }
+        );
+        assert_eq!(errors[0].instance_path, "/network/vlans/vlan200/lynk");
+        assert_eq!(errors[0].instance, Some(Value::from("eth0")));
+
+        let result = validate(
+            schema,
+            r#"
+            network:
+              version: 42
+        "#,
+        );
+        let errors = result.err().unwrap();
+        assert_eq!(
+            errors[0].kind,
+            ErrorKind::OutOfRange {
+                minimum: Some(Value::from(2)),
+                maximum: Some(Value::from(2)),
+            }
+        );
+        assert_eq!(errors[0].instance_path, "/network/version");
+        assert_eq!(errors[0].instance, Some(Value::from(42)));
+        assert_eq!(
+            errors[0].schema_path,
+            "/properties/network/properties/version/maximum"
+        );
+
+        let result = validate(schema, "network: [");
+        assert!(matches!(
+            result.err().unwrap()[0].kind,
+            ErrorKind::ParseError { .. }
+        ));
+    }
+
+    #[test]
+    fn test_error_location() {
+        let schema = Validator::global().schema();
+        let yaml = r#"network:
+  version: 2
+  ethernets:
+    eth0:
+      match:
+        macaddress: a0:4b:xy:1d:ee:0a
+      addresses:
+        - 10.0.0.1/24
+        - 10.0.0.1/24
+  vlans:
+    vlan200:
+      lynk: eth0
+"#;
+        let errors = validate(schema, yaml).err().unwrap();
+        assert_eq!(errors.len(), 3);
+
+        let find = |path: &str| {
+            errors
+                .iter()
+                .find(|e| e.instance_path == path)
+                .unwrap()
+                .span
+                .unwrap()
+        };
+
+        let span = find("/network/ethernets/eth0/match/macaddress");
+        assert_eq!(
+            span.start,
+            Location {
+                line: 6,
+                column: 21
+            }
+        );
+        assert_eq!(
+            span.end,
+            Location {
+                line: 6,
+                column: 38
+            }
+        );
+
+        let span = find("/network/ethernets/eth0/addresses");
+        assert_eq!(span.start, Location { line: 8, column: 9 });
+
+        let span = find("/network/vlans/vlan200/lynk");
+        assert_eq!(
+            span.start,
+            Location {
+                line: 12,
+                column: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let source = SourceMap::new(
+            r#"network:
+  ethernets:
+    "eth0/1": {}
+    eth1:
+      addresses: [10.0.0.1/24, {10.0.0.2/24: {label: a}}]
+"#,
+        );
+
+        let span = source.value("/network/ethernets/eth0~11").unwrap();
+        assert_eq!(
+            span.start,
+            Location {
+                line: 3,
+                column: 15
+            }
+        );
+
+        let span = source.key("/network/ethernets/eth0~11").unwrap();
+        assert_eq!(span.start, Location { line: 3, column: 5 });
+
+        let span = source
+            .value("/network/ethernets/eth1/addresses/1/10.0.0.2~124/label")
+            .unwrap();
+        assert_eq!(
+            span.start,
+            Location {
+                line: 5,
+                column: 54
+            }
+        );
+
+        // Nodes that don't exist resolve to their closest ancestor.
+        let span = source.value("/network/ethernets/eth1/dhcp4").unwrap();
+        assert_eq!(span.start, Location { line: 5, column: 7 });
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let schema = Validator::global().schema();
+
+        let errors = validate(schema, include_str!("../tests/tab_indentation.yaml"))
+            .err()
+            .unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location(), Some(Location { line: 5, column: 1 }));
+        assert_eq!(
+            errors[0].to_string(),
+            "Invalid YAML: found character that cannot start any token at line 5 column 1, \
+             while scanning for the next token"
+        );
+
+        let errors = validate(schema, include_str!("../tests/unclosed_quote.yaml"))
+            .err()
+            .unwrap();
+        assert_eq!(errors[0].location(), Some(Location { line: 8, column: 1 }));
+        assert!(errors[0]
+            .to_string()
+            .contains("while scanning a quoted scalar at line 6 column 11"));
+
+        let errors = validate(schema, include_str!("../tests/bad_indentation.yaml"))
+            .err()
+            .unwrap();
+        assert_eq!(errors[0].location(), Some(Location { line: 6, column: 6 }));
+        assert!(errors[0]
+            .to_string()
+            .starts_with("Invalid YAML: did not find expected key"));
+    }
+
+    #[test]
+    fn test_unexpected_keyword_suggestions() {
+        let schema = Validator::global().schema();
+        let errors = validate(
+            schema,
+            r#"
+            network:
+              ethernets:
+                eth0:
+                  dhcp-4: true
+                  gateway-4: 10.0.0.1
+                  nameserver:
+                    addresses: [8.8.8.8]
+                  something: else
+              vlans:
+                vlan200:
+                  lynk: eth0
+        "#,
+        )
+        .err()
+        .unwrap();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert!(messages.contains(
+            &"Unexpected keyword /network/ethernets/eth0/dhcp-4, did you mean `dhcp4` or `dhcp6`?"
+                .to_string()
+        ));
+        assert!(messages.contains(
+            &"Unexpected keyword /network/ethernets/eth0/gateway-4, did you mean `gateway4` or `gateway6`?"
+                .to_string()
+        ));
+        assert!(messages.contains(
+            &"Unexpected keyword /network/ethernets/eth0/nameserver, did you mean `nameservers`?"
+                .to_string()
+        ));
+        assert!(messages.contains(
+            &"Unexpected keyword /network/vlans/vlan200/lynk, did you mean `link`?".to_string()
+        ));
+        assert!(
+            messages.contains(&"Unexpected keyword /network/ethernets/eth0/something".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_value_messages() {
+        let schema = Validator::global().schema();
+        let errors = validate(
+            schema,
+            r#"
+            network:
+              version: 3
+              renderer: something
+              ethernets:
+                eth0:
+                  dhcp4: nothanks
+                  mtu: -1
+                  gateway4: 10.0.0.300
+                  macaddress: 00:33:aa:bb:cc
+                  addresses:
+                    - 10.0.0.1/24:
+                        label: aaaaaaaaaaaaaaaa
+        "#,
+        )
+        .err()
+        .unwrap();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+        let expected = [
+            "Unexpected value /network/version: 3, must be between 2 and 2",
+            "Unexpected value /network/renderer: \"something\", expected one of networkd, NetworkManager, sriov",
+            "Unexpected value /network/ethernets/eth0/dhcp4: \"nothanks\", expected a boolean",
+            "Unexpected value /network/ethernets/eth0/mtu: -1, must be at least 0",
+            "Unexpected value /network/ethernets/eth0/gateway4: \"10.0.0.300\", not a valid ipv4",
+            "Unexpected value /network/ethernets/eth0/macaddress: \"00:33:aa:bb:cc\", doesn't match the pattern \
+             ([0-9a-f]{2}):([0-9a-f]{2}):([0-9a-f]{2}):([0-9a-f]{2}):([0-9a-f]{2}):([0-9a-f]{2})",
+            "Unexpected value /network/ethernets/eth0/addresses/0/10.0.0.1~124/label: \"aaaaaaaaaaaaaaaa\", \
+             longer than 15 characters",
+        ];
+        for message in expected {
+            assert!(messages.contains(&message.to_string()), "{message}");
+        }
+        assert_eq!(messages.len(), expected.len());
+    }
+
     #[test]
     fn test_network_valid_version() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               version: 2
@@ -89,9 +483,9 @@ mod tests {
 
     #[test]
     fn test_network_invalid_version() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               version: 3
@@ -102,9 +496,9 @@ mod tests {
 
     #[test]
     fn test_network_invalid_renderer() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               renderer: invalid
@@ -115,9 +509,9 @@ mod tests {
 
     #[test]
     fn test_network_valid_renderer() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               renderer: networkd
@@ -126,7 +520,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               renderer: NetworkManager
@@ -137,9 +531,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_empty() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -151,9 +545,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_property() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -166,9 +560,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_link() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -181,9 +575,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_link() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -196,9 +590,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_virtual_function_count() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -211,9 +605,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_virtual_function_count() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -224,7 +618,7 @@ mod tests {
         assert!(result.is_err());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -237,9 +631,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_embedded_switch_mode() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -250,7 +644,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -263,9 +657,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_embedded_switch_mode() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -276,7 +670,7 @@ mod tests {
         assert!(result.is_err());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -289,9 +683,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_delay_virtual_functions_rebind() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -304,9 +698,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_delay_virtual_functions_rebind() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -319,9 +713,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_infiniband_mode() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -332,7 +726,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -345,9 +739,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_infiniband_mode() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -360,9 +754,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_with_renderer() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -373,7 +767,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -386,9 +780,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_with_invalid_renderer() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -399,7 +793,7 @@ mod tests {
         assert!(result.is_err());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -412,9 +806,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_dhcp() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -428,9 +822,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_dhcp_invalid() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -443,9 +837,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_ipv6_mtu() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -458,9 +852,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_ipv6_mtu() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -471,7 +865,7 @@ mod tests {
         assert!(result.is_err());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -484,9 +878,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_ipv6_privacy() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -499,9 +893,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_ipv6_privacy() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -514,9 +908,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_link_local() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -531,9 +925,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_link_local() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -549,9 +943,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_ignore_carrier() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -564,9 +958,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_ignore_carrier() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -579,9 +973,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_critical() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -594,9 +988,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_critical() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -609,9 +1003,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_dhcp_identifier() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -622,7 +1016,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -635,9 +1029,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_dhcp4_override() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -659,9 +1053,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_dhcp4_override() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -675,9 +1069,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_dhcp6_override() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -699,9 +1093,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_dhcp6_override() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -715,9 +1109,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_accept_ra() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -730,9 +1124,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_accept_ra() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -745,9 +1139,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_addresses() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -763,7 +1157,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -781,9 +1175,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_non_unique_addresses() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -797,10 +1191,10 @@ mod tests {
         "#,
         );
         assert!(result.is_err());
-        assert!(result.err().unwrap().starts_with("Duplicate item"));
+        assert_eq!(result.err().unwrap()[0].kind, ErrorKind::DuplicateItem);
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -818,14 +1212,14 @@ mod tests {
         );
         assert!(result.is_err());
 
-        assert!(result.err().unwrap().starts_with("Duplicate item"));
+        assert_eq!(result.err().unwrap()[0].kind, ErrorKind::DuplicateItem);
     }
 
     #[test]
     fn test_ethernet_ipv6_address_generation() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -836,7 +1230,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -849,9 +1243,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_ipv6_address_generation() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -864,9 +1258,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_gateway4() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -879,9 +1273,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_gateway6() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -894,9 +1288,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_gateway4() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -909,9 +1303,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_gateway6() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -924,9 +1318,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_nameservers() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -946,9 +1340,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_nameservers() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -967,7 +1361,7 @@ mod tests {
         assert!(result.is_err());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -982,9 +1376,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_macaddress() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -997,9 +1391,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_macaddress() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1012,9 +1406,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_mtu() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1027,9 +1421,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_mtu() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1040,7 +1434,7 @@ mod tests {
         assert!(result.is_err());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1053,9 +1447,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_optional() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1068,9 +1462,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_optional() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1083,9 +1477,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_optional_addresses() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1103,9 +1497,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_optional_addresses() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1119,9 +1513,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_activation_mode() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1132,7 +1526,7 @@ mod tests {
         assert!(result.is_ok());
 
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1145,9 +1539,9 @@ mod tests {
 
     #[test]
     fn test_ethernet_invalid_activation_mode() {
-        let schema = schema::build_schema().unwrap();
+        let schema = Validator::global().schema();
         let result = validate(
-            &schema,
+            schema,
             r#"
             network:
               ethernets:
@@ -1157,4 +1551,439 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_bond() {
+        let schema = Validator::global().schema();
+        let result = validate(
+            schema,
+            r#"
+            network:
+              version: 2
+              ethernets:
+                eth0: {}
+                eth1: {}
+              bonds:
+                bond0:
+                  dhcp4: true
+                  interfaces: [eth0, eth1]
+                  parameters:
+                    mode: 802.3ad
+                    lacp-rate: fast
+                    mii-monitor-interval: 100
+                    min-links: 1
+                    transmit-hash-policy: layer3+4
+                    ad-select: bandwidth
+                    all-members-active: false
+                    up-delay: 200ms
+                    down-delay: 1s
+                    resend-igmp: 1
+                    learn-packet-interval: 1
+        "#,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_bond_active_backup() {
+        let schema = Validator::global().schema();
+        let result = validate(
+            schema,
+            r#"
+            network:
+              bonds:
+                bond0:
+                  interfaces: [eth0, eth1]
+                  parameters:
+                    mode: active-backup
+                    primary: eth0
+                    primary-reselect-policy: better
+                    fail-over-mac-policy: active
+                    gratuitous-arp: 5
+                    arp-interval: 100
+                    arp-ip-targets: [10.0.0.1, 10.0.0.2]
+                    arp-validate: all
+                    arp-all-targets: any
+                    packets-per-member: 0
+        "#,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_bond_invalid_parameters() {
+        let schema = Validator::global().schema();
+        let errors = validate(
+            schema,
+            r#"
+            network:
+              bonds:
+                bond0:
+                  interfaces: [eth0, eth0]
+                  parameters:
+                    mode: round-robin
+                    lacp-rate: medium
+                    mii-monitor-interval: soon
+                    min-links: -1
+                    gratuitous-arp: 0
+                    packets-per-member: 65536
+                    resend-igmp: 256
+                    arp-ip-targets: [10.0.0.256]
+                    all-members-active: yes please
+                    primary-slave: eth0
+        "#,
+        )
+        .unwrap_err();
+
+        let mut paths: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.kind.name()))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                ("/network/bonds/bond0/interfaces", "duplicate-item"),
+                (
+                    "/network/bonds/bond0/parameters/all-members-active",
+                    "invalid-type"
+                ),
+                (
+                    "/network/bonds/bond0/parameters/arp-ip-targets/0",
+                    "invalid-format"
+                ),
+                (
+                    "/network/bonds/bond0/parameters/gratuitous-arp",
+                    "out-of-range"
+                ),
+                ("/network/bonds/bond0/parameters/lacp-rate", "invalid-enum"),
+                (
+                    "/network/bonds/bond0/parameters/mii-monitor-interval",
+                    "pattern-mismatch"
+                ),
+                ("/network/bonds/bond0/parameters/min-links", "out-of-range"),
+                ("/network/bonds/bond0/parameters/mode", "invalid-enum"),
+                (
+                    "/network/bonds/bond0/parameters/packets-per-member",
+                    "out-of-range"
+                ),
+                (
+                    "/network/bonds/bond0/parameters/primary-slave",
+                    "unexpected-keyword"
+                ),
+                (
+                    "/network/bonds/bond0/parameters/resend-igmp",
+                    "out-of-range"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bond_too_many_arp_ip_targets() {
+        let schema = Validator::global().schema();
+        let targets: Vec<String> = (1..=17).map(|i| format!("10.0.0.{i}")).collect();
+        let result = validate(
+            schema,
+            &format!(
+                "network:\n  bonds:\n    bond0:\n      parameters:\n        arp-ip-targets: [{}]\n",
+                targets.join(", ")
+            ),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_tunnels() {
+        let schema = Validator::global().schema();
+        let result = validate(
+            schema,
+            r#"
+            network:
+              version: 2
+              tunnels:
+                gre0:
+                  mode: gre
+                  local: 10.0.0.1
+                  remote: 10.0.0.2
+                  ttl: 64
+                  keys:
+                    input: 1234
+                    output: 5678
+                sit0:
+                  mode: sit
+                  remote: 10.0.0.3
+                  key: 10.0.0.4
+                vxlan0:
+                  mode: vxlan
+                  id: 1000
+                  link: eth0
+                  local: 10.0.0.1
+                  remote: 239.0.0.1
+                  port: 4789
+                  mac-learning: true
+                  ageing: 300
+                  limit: 100
+                  arp-proxy: true
+                  neigh-suppress: true
+                  notifications: [l2-miss, l3-miss]
+                  short-circuit: true
+                  checksums: [udp, remote-rx]
+                  extensions: [group-policy]
+                  port-range: [4000, 5000]
+                  flow-label: 42
+                  do-not-fragment: true
+                wg0:
+                  mode: wireguard
+                  port: 51820
+                  mark: 42
+                  key: /etc/wireguard/private.key
+                  addresses: [10.10.0.1/24]
+                  peers:
+                    - endpoint: 203.0.113.1:51820
+                      allowed-ips: [10.10.0.0/24]
+                      keepalive: 25
+                      keys:
+                        public: /etc/wireguard/peer.pub
+                        shared: /etc/wireguard/peer.psk
+        "#,
+        );
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[test]
+    fn test_tunnel_invalid_values() {
+        let schema = Validator::global().schema();
+        let errors = validate(
+            schema,
+            r#"
+            network:
+              tunnels:
+                tun0:
+                  local: 10.0.0.1
+                tun1:
+                  mode: gre6
+                tun2:
+                  mode: vxlan
+                  id: 16777216
+                  ttl: 0
+                  port-range: [4000]
+                  notifications: [l4-miss]
+        "#,
+        )
+        .unwrap_err();
+
+        let mut paths: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.kind.name()))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                ("/network/tunnels/tun0/mode", "missing-keyword"),
+                ("/network/tunnels/tun1/mode", "invalid-enum"),
+                ("/network/tunnels/tun2/id", "out-of-range"),
+                ("/network/tunnels/tun2/notifications/0", "invalid-enum"),
+                ("/network/tunnels/tun2/port-range", "invalid-value"),
+                ("/network/tunnels/tun2/ttl", "out-of-range"),
+            ]
+        );
+
+        let missing = errors
+            .iter()
+            .find(|e| e.kind.name() == "missing-keyword")
+            .unwrap();
+        assert_eq!(
+            missing.to_string(),
+            "Missing keyword /network/tunnels/tun0/mode"
+        );
+        // Points to the tunnel, the keyword itself isn't there.
+        assert_eq!(missing.location().unwrap().line, 5);
+    }
+
+    #[test]
+    fn test_tunnel_settings_of_other_modes() {
+        let schema = Validator::global().schema();
+        let errors = validate(
+            schema,
+            r#"network:
+  tunnels:
+    gre0:
+      mode: gre
+      port: 4789
+      mac-learning: true
+      peers: []
+      keys:
+        private: /etc/wireguard/private.key
+    wg0:
+      mode: wireguard
+      keys:
+        input: 1234
+      link: eth0
+"#,
+        )
+        .unwrap_err();
+
+        let mut messages: Vec<String> = errors
+            .iter()
+            .map(|e| format!("{}: {e}", e.location().unwrap().line))
+            .collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "13: Unexpected keyword /network/tunnels/wg0/keys/input",
+                "14: Unexpected keyword /network/tunnels/wg0/link",
+                "5: Unexpected keyword /network/tunnels/gre0/port",
+                "6: Unexpected keyword /network/tunnels/gre0/mac-learning",
+                "7: Unexpected keyword /network/tunnels/gre0/peers",
+                "9: Unexpected keyword /network/tunnels/gre0/keys/private",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wireguard_keys_and_peers() {
+        let schema = Validator::global().schema();
+        assert!(validate(
+            schema,
+            r#"
+            network:
+              tunnels:
+                wg0:
+                  mode: wireguard
+                  key: 4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnOm8=
+                  peers:
+                    - endpoint: "[2001:db8::1]:51820"
+                      allowed-ips: [0.0.0.0/0, "::/0"]
+                      keepalive: 25
+                      keys:
+                        public: M9nt4YujIOmNrRmpIRTmYSfMdrpvE7u6WkG8FY8WjG4=
+                        shared: /etc/wireguard/psk.key
+                wg1:
+                  mode: wireguard
+                  keys:
+                    private: /etc/wireguard/private.key
+        "#,
+        )
+        .is_ok());
+
+        let errors = validate(
+            schema,
+            r#"network:
+  tunnels:
+    wg0:
+      mode: wireguard
+      key: not-a-key
+      peers:
+        - endpoint: vpn.example.com
+          allowed-ips: [10.0.0.0/33, 10.1.0.0/16]
+          keepalive: 65536
+          keys:
+            public: 4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnOm8
+    wg1:
+      mode: wireguard
+      keys:
+        private: 42
+    gre0:
+      mode: gre
+      key: 42
+"#,
+        )
+        .unwrap_err();
+
+        let mut messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "Unexpected value /network/tunnels/wg0/key: \"not-a-key\", not a valid wireguard-key",
+                "Unexpected value /network/tunnels/wg0/peers/0/allowed-ips/0: \"10.0.0.0/33\", not a valid cidr",
+                "Unexpected value /network/tunnels/wg0/peers/0/endpoint: \"vpn.example.com\", not a valid endpoint",
+                "Unexpected value /network/tunnels/wg0/peers/0/keepalive: 65536, must be between 0 and 65535",
+                "Unexpected value /network/tunnels/wg0/peers/0/keys/public: \"4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnOm8\", not a valid wireguard-key",
+                "Unexpected value /network/tunnels/wg1/keys/private: 42, expected a string",
+            ]
+        );
+        let key = errors
+            .iter()
+            .find(|e| e.instance_path == "/network/tunnels/wg0/key")
+            .unwrap();
+        assert_eq!(key.kind.name(), "invalid-format");
+        assert_eq!(key.location().unwrap().line, 5);
+    }
+
+    #[test]
+    fn test_modems() {
+        let schema = Validator::global().schema();
+        assert!(validate(
+            schema,
+            r#"
+            network:
+              renderer: NetworkManager
+              modems:
+                cdc-wdm1:
+                  apn: internet
+                  auto-config: false
+                  device-id: "0x1234"
+                  network-id: "310260"
+                  number: "*99#"
+                  username: user
+                  password: secret
+                  pin: "1234"
+                  sim-id: "89014103211118510720"
+                  sim-operator-id: "310260"
+                  dhcp4: true
+                  mtu: 1500
+        "#,
+        )
+        .is_ok());
+
+        let errors = validate(
+            schema,
+            r#"network:
+  modems:
+    cdc-wdm1:
+      auto-config: yes please
+      sim-operatorid: "310260"
+"#,
+        )
+        .unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unexpected value /network/modems/cdc-wdm1/auto-config: \"yes please\", expected a boolean",
+                "Unexpected keyword /network/modems/cdc-wdm1/sim-operatorid, did you mean `sim-operator-id`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vrf_routing_policy() {
+        let schema = Validator::global().schema();
+        let errors = validate(
+            schema,
+            r#"network:
+  vrfs:
+    vrf0:
+      table: 42
+      routing-policy:
+        - from: 10.0.0.0/24
+          table: 43
+        - from: 10.0.1.0/24
+          table: 42
+"#,
+        )
+        .unwrap_err();
+
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec!["Unexpected value /network/vrfs/vrf0/routing-policy/0/table: 43, expected 42 as in /network/vrfs/vrf0/table"]
+        );
+        assert_eq!(errors[0].kind.name(), "mismatched-value");
+        assert_eq!(errors[0].location().unwrap().line, 7);
+    }
 }
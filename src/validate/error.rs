@@ -0,0 +1,222 @@
+use serde_json::value::Value;
+use std::fmt;
+
+use super::location::{Location, Origin, Span};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// A property that isn't allowed at that location, along with the closest valid ones.
+    UnexpectedKeyword {
+        keyword: String,
+        suggestions: Vec<String>,
+    },
+    /// A required property that isn't there.
+    MissingKeyword { keyword: String },
+    /// An item repeated in an array that requires unique items.
+    DuplicateItem,
+    /// A value that isn't one of the allowed options.
+    InvalidEnum { options: Vec<Value> },
+    /// A number outside of the allowed range.
+    OutOfRange {
+        minimum: Option<Value>,
+        maximum: Option<Value>,
+    },
+    /// A string that doesn't match the expected pattern.
+    PatternMismatch { pattern: String },
+    /// A string that isn't in the expected format, e.g. an IPv4 address.
+    InvalidFormat { format: String },
+    /// A value of the wrong type.
+    InvalidType { expected: Vec<String> },
+    /// A string longer than allowed.
+    TooLong { limit: u64 },
+    /// A value that has to match the one of another node, e.g. the table of a route in a VRF.
+    MismatchedValue {
+        expected: Value,
+        /// JSON pointer to the node holding the expected value.
+        reference: String,
+    },
+    /// A value rejected by the schema for any other reason.
+    InvalidValue,
+    /// The input couldn't be parsed as YAML.
+    ParseError { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// JSON pointer to the offending node, e.g. /network/ethernets/eth0/dhcp4.
+    pub instance_path: String,
+    /// The offending value, if there is one.
+    pub instance: Option<Value>,
+    /// JSON pointer to the schema keyword that rejected the value.
+    pub schema_path: String,
+    /// Where the offending node is in the YAML source.
+    pub span: Option<Span>,
+    /// Files that set the offending node, when validating a merged configuration.
+    pub origins: Vec<Origin>,
+}
+
+impl Error {
+    pub fn parse_error(error: &serde_yaml::Error) -> Self {
+        let span = error.location().map(|l| {
+            let location = Location {
+                line: l.line(),
+                column: l.column(),
+            };
+            Span {
+                start: location,
+                end: location,
+            }
+        });
+
+        Error {
+            kind: ErrorKind::ParseError {
+                message: error.to_string(),
+            },
+            instance_path: String::new(),
+            instance: None,
+            schema_path: String::new(),
+            span,
+            origins: Vec::new(),
+        }
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        self.span.map(|span| span.start)
+    }
+}
+
+impl ErrorKind {
+    /// Stable identifier of the kind, used in machine-readable output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedKeyword { .. } => "unexpected-keyword",
+            ErrorKind::MissingKeyword { .. } => "missing-keyword",
+            ErrorKind::DuplicateItem => "duplicate-item",
+            ErrorKind::InvalidEnum { .. } => "invalid-enum",
+            ErrorKind::OutOfRange { .. } => "out-of-range",
+            ErrorKind::PatternMismatch { .. } => "pattern-mismatch",
+            ErrorKind::InvalidFormat { .. } => "invalid-format",
+            ErrorKind::InvalidType { .. } => "invalid-type",
+            ErrorKind::TooLong { .. } => "too-long",
+            ErrorKind::MismatchedValue { .. } => "mismatched-value",
+            ErrorKind::InvalidValue => "invalid-value",
+            ErrorKind::ParseError { .. } => "parse-error",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let instance = self.instance.as_ref().unwrap_or(&Value::Null);
+
+        match &self.kind {
+            ErrorKind::UnexpectedKeyword { suggestions, .. } => {
+                write!(f, "Unexpected keyword {}", self.instance_path)?;
+
+                if let Some((last, rest)) = suggestions.split_last() {
+                    write!(f, ", did you mean ")?;
+                    for (i, suggestion) in rest.iter().enumerate() {
+                        let separator = if i + 1 == rest.len() { " or " } else { ", " };
+                        write!(f, "`{suggestion}`{separator}")?;
+                    }
+                    write!(f, "`{last}`?")?;
+                }
+
+                Ok(())
+            }
+            ErrorKind::MissingKeyword { .. } => {
+                write!(f, "Missing keyword {}", self.instance_path)
+            }
+            ErrorKind::DuplicateItem => {
+                write!(f, "Duplicate item {}/{}", self.instance_path, instance)
+            }
+            ErrorKind::MismatchedValue {
+                expected,
+                reference,
+            } => {
+                write!(
+                    f,
+                    "Unexpected value {}: {}, expected {expected} as in {reference}",
+                    self.instance_path, instance
+                )
+            }
+            ErrorKind::InvalidValue => {
+                write!(f, "Unexpected value {}: {}", self.instance_path, instance)
+            }
+            ErrorKind::InvalidEnum { options } => {
+                let options: Vec<String> = options.iter().map(plain).collect();
+                write!(
+                    f,
+                    "Unexpected value {}: {}, expected one of {}",
+                    self.instance_path,
+                    instance,
+                    options.join(", ")
+                )
+            }
+            ErrorKind::OutOfRange { minimum, maximum } => {
+                write!(f, "Unexpected value {}: {}, ", self.instance_path, instance)?;
+                match (minimum, maximum) {
+                    (Some(min), Some(max)) => write!(f, "must be between {min} and {max}"),
+                    (Some(min), None) => write!(f, "must be at least {min}"),
+                    (None, Some(max)) => write!(f, "must be at most {max}"),
+                    (None, None) => write!(f, "out of range"),
+                }
+            }
+            ErrorKind::PatternMismatch { pattern } => {
+                write!(
+                    f,
+                    "Unexpected value {}: {}, doesn't match the pattern {pattern}",
+                    self.instance_path, instance
+                )
+            }
+            ErrorKind::InvalidFormat { format } => {
+                write!(
+                    f,
+                    "Unexpected value {}: {}, not a valid {format}",
+                    self.instance_path, instance
+                )
+            }
+            ErrorKind::InvalidType { expected } => {
+                let expected: Vec<String> = expected.iter().map(|t| article(t)).collect();
+                write!(
+                    f,
+                    "Unexpected value {}: {}, expected {}",
+                    self.instance_path,
+                    instance,
+                    expected.join(" or ")
+                )
+            }
+            ErrorKind::TooLong { limit } => {
+                write!(
+                    f,
+                    "Unexpected value {}: {}, longer than {limit} characters",
+                    self.instance_path, instance
+                )
+            }
+            ErrorKind::ParseError { message } => write!(f, "Invalid YAML: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Strings are printed without quotes in lists of options.
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+fn article(noun: &str) -> String {
+    match noun.chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => format!("an {noun}"),
+        _ => format!("a {noun}"),
+    }
+}
+
+// Appends a key to a JSON pointer, escaping it as described in RFC 6901.
+pub(crate) fn pointer_push(pointer: &str, key: &str) -> String {
+    format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
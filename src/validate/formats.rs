@@ -0,0 +1,153 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+type Format = fn(&str) -> bool;
+
+// Formats that aren't part of JSON schema, registered when compiling the schema. Like the
+// standard ones, they only apply to strings.
+pub const FORMATS: &[(&str, Format)] = &[
+    ("wireguard-key", is_wireguard_key),
+    ("endpoint", is_endpoint),
+    ("cidr", is_cidr),
+];
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// A WireGuard key is 32 bytes encoded in base64, i.e. 43 characters and a single padding
+// character, or the absolute path of a file holding it.
+pub fn is_wireguard_key(key: &str) -> bool {
+    if key.starts_with('/') {
+        return key.len() > 1;
+    }
+
+    let encoded = match key.strip_suffix('=') {
+        Some(encoded) if encoded.len() == 43 => encoded.as_bytes(),
+        _ => return false,
+    };
+
+    if !encoded.iter().all(|c| BASE64.contains(c)) {
+        return false;
+    }
+
+    // The last character only carries 4 bits of the key, the 2 bits left must be zero.
+    let last = BASE64.iter().position(|b| *b == encoded[42]);
+    matches!(last, Some(last) if last % 4 == 0)
+}
+
+// host:port, where host is a host name or an IPv4 address, or [IPv6 address]:port.
+pub fn is_endpoint(endpoint: &str) -> bool {
+    let (host, port) = match endpoint.rsplit_once(':') {
+        Some(split) => split,
+        None => return false,
+    };
+
+    if !matches!(port.parse::<u16>(), Ok(port) if port > 0) {
+        return false;
+    }
+
+    match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        Some(address) => address.parse::<Ipv6Addr>().is_ok(),
+        None => is_hostname_or_ipv4(host),
+    }
+}
+
+fn is_hostname_or_ipv4(host: &str) -> bool {
+    // Only digits, e.g. 999.1.1.1 or 1.2.3, can't be a host name and has to be an address.
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return host.parse::<Ipv4Addr>().is_ok();
+    }
+
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+pub fn is_cidr(network: &str) -> bool {
+    parse_cidr(network).is_some()
+}
+
+// Splits an address with a prefix length, e.g. 10.0.0.0/8, into its parts.
+pub fn parse_cidr(network: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = network.split_once('/')?;
+    let address: IpAddr = address.parse().ok()?;
+    // Only plain decimal numbers, parse() would also accept a leading +.
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let prefix: u8 = prefix.parse().ok()?;
+
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    (prefix <= bits).then_some((address, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wireguard_key() {
+        assert!(is_wireguard_key(
+            "4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnOm8="
+        ));
+        assert!(is_wireguard_key("/etc/wireguard/private.key"));
+
+        assert!(!is_wireguard_key(""));
+        assert!(!is_wireguard_key("/"));
+        assert!(!is_wireguard_key("wireguard/private.key"));
+        // Too short, missing padding, invalid character, non-zero trailing bits.
+        assert!(!is_wireguard_key(
+            "4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnO="
+        ));
+        assert!(!is_wireguard_key(
+            "4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnOm8"
+        ));
+        assert!(!is_wireguard_key(
+            "4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWn*m8="
+        ));
+        assert!(!is_wireguard_key(
+            "4GgaQCy68nzNsUE5aJ9fuLzHhB65tAlwbmA72MWnOm9="
+        ));
+    }
+
+    #[test]
+    fn test_endpoint() {
+        assert!(is_endpoint("vpn.example.com:51820"));
+        assert!(is_endpoint("1.2.3.4:51820"));
+        assert!(is_endpoint("[2001:db8::1]:51820"));
+
+        assert!(!is_endpoint("vpn.example.com"));
+        assert!(!is_endpoint("vpn.example.com:0"));
+        assert!(!is_endpoint("vpn.example.com:65536"));
+        assert!(!is_endpoint(":51820"));
+        assert!(!is_endpoint("vpn..example.com:51820"));
+        assert!(!is_endpoint("-vpn.example.com:51820"));
+        assert!(!is_endpoint("2001:db8::1:51820"));
+        assert!(!is_endpoint("[vpn.example.com]:51820"));
+        assert!(!is_endpoint("999.1.1.1:51820"));
+        assert!(!is_endpoint("1.2.3:51820"));
+        assert!(!is_endpoint("1.2.3.4.:51820"));
+    }
+
+    #[test]
+    fn test_cidr() {
+        assert_eq!(
+            parse_cidr("10.0.0.0/8"),
+            Some(("10.0.0.0".parse().unwrap(), 8))
+        );
+        assert!(is_cidr("0.0.0.0/0"));
+        assert!(is_cidr("2001:db8::/32"));
+        assert!(is_cidr("::/0"));
+
+        assert!(!is_cidr("10.0.0.1"));
+        assert!(!is_cidr("10.0.0.0/33"));
+        assert!(!is_cidr("2001:db8::/129"));
+        assert!(!is_cidr("10.0.0.0/"));
+        assert!(!is_cidr("10.0.0.0/+8"));
+        assert!(!is_cidr("10.0.0/8"));
+    }
+}
@@ -0,0 +1,181 @@
+use saphyr_parser::{Event, Parser};
+use std::collections::HashMap;
+
+use super::error::pointer_push;
+
+// serde_yaml doesn't keep track of where each value came from, so the YAML is parsed a
+// second time with a lower level parser and the position of every node is indexed by its
+// JSON pointer. That's the same pointer jsonschema reports in instance_path.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number.
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+// Where a node of a merged configuration was set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    pub file: String,
+    pub span: Option<Span>,
+}
+
+impl From<saphyr_parser::Span> for Span {
+    fn from(span: saphyr_parser::Span) -> Self {
+        Span {
+            start: Location {
+                line: span.start.line(),
+                column: span.start.col() + 1,
+            },
+            end: Location {
+                line: span.end.line(),
+                column: span.end.col() + 1,
+            },
+        }
+    }
+}
+
+enum Key {
+    Expected,
+    Scalar(String),
+    // Complex mapping keys can't be represented in a JSON pointer, their value is skipped.
+    Complex,
+}
+
+enum Frame {
+    Mapping {
+        pointer: String,
+        start: Location,
+        key: Key,
+    },
+    Sequence {
+        pointer: String,
+        start: Location,
+        index: usize,
+    },
+    // Content of a complex mapping key or of its value.
+    Ignored,
+}
+
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    values: HashMap<String, Span>,
+    keys: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    pub fn new(yaml: &str) -> SourceMap {
+        let mut map = SourceMap::default();
+        let mut stack: Vec<Frame> = Vec::new();
+
+        for result in Parser::new_from_str(yaml) {
+            let Ok((event, span)) = result else {
+                break;
+            };
+
+            match event {
+                Event::DocumentEnd => break,
+                Event::Scalar(value, ..) => {
+                    if let Some(Frame::Mapping {
+                        pointer,
+                        key: key @ Key::Expected,
+                        ..
+                    }) = stack.last_mut()
+                    {
+                        map.keys.insert(pointer_push(pointer, &value), span.into());
+                        *key = Key::Scalar(value.into_owned());
+                    } else if let Some(pointer) = next_pointer(&mut stack) {
+                        map.values.insert(pointer, span.into());
+                    }
+                }
+                Event::Alias(..) => {
+                    if let Some(pointer) = next_pointer(&mut stack) {
+                        map.values.insert(pointer, span.into());
+                    }
+                }
+                Event::MappingStart(..) => {
+                    let frame = match next_pointer(&mut stack) {
+                        Some(pointer) => Frame::Mapping {
+                            pointer,
+                            start: Span::from(span).start,
+                            key: Key::Expected,
+                        },
+                        None => Frame::Ignored,
+                    };
+                    stack.push(frame);
+                }
+                Event::SequenceStart(..) => {
+                    let frame = match next_pointer(&mut stack) {
+                        Some(pointer) => Frame::Sequence {
+                            pointer,
+                            start: Span::from(span).start,
+                            index: 0,
+                        },
+                        None => Frame::Ignored,
+                    };
+                    stack.push(frame);
+                }
+                Event::MappingEnd | Event::SequenceEnd => match stack.pop() {
+                    Some(Frame::Mapping { pointer, start, .. })
+                    | Some(Frame::Sequence { pointer, start, .. }) => {
+                        let end = Span::from(span).end;
+                        map.values.insert(pointer, Span { start, end });
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        map
+    }
+
+    /// Span of the node at `pointer`, or of its closest ancestor present in the document.
+    pub fn value(&self, pointer: &str) -> Option<Span> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(span) = self.values.get(pointer) {
+                return Some(*span);
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    /// Span of the mapping key at `pointer`, falling back to the value's span.
+    pub fn key(&self, pointer: &str) -> Option<Span> {
+        self.keys
+            .get(pointer)
+            .copied()
+            .or_else(|| self.value(pointer))
+    }
+}
+
+// Works out the pointer of the node that is about to start and advances its parent.
+// Returns None if the node is a complex mapping key, its value, or lives inside one of them.
+fn next_pointer(stack: &mut [Frame]) -> Option<String> {
+    match stack.last_mut() {
+        None => Some(String::new()),
+        Some(Frame::Mapping { key, pointer, .. }) => match std::mem::replace(key, Key::Expected) {
+            Key::Scalar(key) => Some(pointer_push(pointer, &key)),
+            Key::Expected => {
+                *key = Key::Complex;
+                None
+            }
+            Key::Complex => None,
+        },
+        Some(Frame::Sequence { pointer, index, .. }) => {
+            let pointer = pointer_push(pointer, &index.to_string());
+            *index += 1;
+            Some(pointer)
+        }
+        Some(Frame::Ignored) => None,
+    }
+}
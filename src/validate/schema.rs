@@ -1,99 +1,74 @@
 use jsonschema::{Draft, JSONSchema};
 use serde_json::value::Value;
+use std::sync::OnceLock;
 
-// Apparently, inheritance has some limitation in jsonschema.
-// When trying to reuse blocks of definitions and expanding the properties
-// of objects while keeping the "additionalProperties: false", I realized it doesn't work
-// as I expected. The $reference of definitions work like a charm, but it will allow the user
-// to insert additional properties in the YAML and we can't allow that.
-//
-// My workaround is dirty: it will load the base schema, get a pointer to the properties we
-// want to expand and inject the common properties used by all the interface types there.
+use super::formats::FORMATS;
 
 pub fn build_schema() -> Result<JSONSchema, String> {
-    let mut schema_data: Value = serde_yaml::from_str(SCHEMA).unwrap();
+    let schema_data = schema_value();
 
-    let common_properties_patch: Value = serde_yaml::from_str(COMMON_PROPERTIES).unwrap();
-
-    let ethernet_properties = schema_data
-        .pointer_mut("/properties/network/properties/ethernets/patternProperties/.*$/properties");
-    if let Some(props) = ethernet_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
-        }
+    let mut options = JSONSchema::options();
+    options.with_draft(Draft::Draft7);
+    for &(name, format) in FORMATS {
+        options.with_format(name, format);
     }
+    let schema_result = options.compile(schema_data);
 
-    let vlan_properties = schema_data
-        .pointer_mut("/properties/network/properties/vlans/patternProperties/.*$/properties");
-
-    if let Some(props) = vlan_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
-        }
-    }
-
-    let bridge_properties = schema_data
-        .pointer_mut("/properties/network/properties/bridges/patternProperties/.*$/properties");
-
-    if let Some(props) = bridge_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
-        }
-    }
-
-    let wifis_properties = schema_data
-        .pointer_mut("/properties/network/properties/wifis/patternProperties/.*$/properties");
-
-    if let Some(props) = wifis_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
-        }
+    if let Err(ref err) = schema_result {
+        let error = format!("{}, {:?}, {}", err.schema_path, err.kind, err.instance_path);
+        return Err(error);
     }
 
-    let bonds_properties = schema_data
-        .pointer_mut("/properties/network/properties/bonds/patternProperties/.*$/properties");
+    Ok(schema_result.unwrap())
+}
 
-    if let Some(props) = bonds_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
-        }
-    }
+// The schema never changes, so it's only built the first time it's needed.
+pub fn schema_value() -> &'static Value {
+    static SCHEMA: OnceLock<Value> = OnceLock::new();
+    SCHEMA.get_or_init(build_schema_value)
+}
 
-    let tunnels_properties = schema_data
-        .pointer_mut("/properties/network/properties/tunnels/patternProperties/.*$/properties");
+// Device types that get the common properties, nm-devices are passed to NetworkManager as is.
+const PATCHED_DEVICE_TYPES: &[&str] = &[
+    "ethernets",
+    "modems",
+    "wifis",
+    "bridges",
+    "bonds",
+    "tunnels",
+    "vlans",
+    "vrfs",
+];
 
-    if let Some(props) = tunnels_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
-        }
-    }
+// Apparently, inheritance has some limitation in jsonschema.
+// When trying to reuse blocks of definitions and expanding the properties
+// of objects while keeping the "additionalProperties: false", I realized it doesn't work
+// as I expected. The $reference of definitions work like a charm, but it will allow the user
+// to insert additional properties in the YAML and we can't allow that.
+//
+// My workaround is dirty: it will load the base schema, get a pointer to the properties we
+// want to expand and inject the common properties used by all the interface types there.
+//
+// The resulting schema is also used to look up the properties allowed at a given location
+// when suggesting alternatives to unexpected keywords.
+pub fn build_schema_value() -> Value {
+    let mut schema_data: Value = serde_yaml::from_str(SCHEMA).unwrap();
 
-    let vrfs_properties = schema_data
-        .pointer_mut("/properties/network/properties/vrfs/patternProperties/.*$/properties");
+    let common_properties_patch: Value = serde_yaml::from_str(COMMON_PROPERTIES).unwrap();
 
-    if let Some(props) = vrfs_properties {
-        let properties = props.as_object_mut().unwrap();
-        for (k, v) in common_properties_patch.as_object().unwrap() {
-            properties.insert(k.clone(), v.clone());
+    for device_type in PATCHED_DEVICE_TYPES {
+        let pointer = format!(
+            "/properties/network/properties/{device_type}/patternProperties/.*$/properties"
+        );
+        if let Some(props) = schema_data.pointer_mut(&pointer) {
+            let properties = props.as_object_mut().unwrap();
+            for (k, v) in common_properties_patch.as_object().unwrap() {
+                properties.insert(k.clone(), v.clone());
+            }
         }
     }
 
-    let schema_result = JSONSchema::options()
-        .with_draft(Draft::Draft7)
-        .compile(&schema_data);
-
-    if let Err(ref err) = schema_result {
-        let error = format!("{}, {:?}, {}", err.schema_path, err.kind, err.instance_path);
-        return Err(error);
-    }
-
-    return Ok(schema_result.unwrap());
+    schema_data
 }
 
 pub const SCHEMA: &str = r#"
@@ -211,9 +186,30 @@ properties:
 
         patternProperties:
           # network.modems.<interface>
+          # GSM and CDMA modems, only supported by NetworkManager.
           ".*$":
             additionalProperties: false
-            properties: {}
+            properties:
+              apn:
+                type: string
+              auto-config:
+                type: boolean
+              device-id:
+                type: string
+              network-id:
+                type: string
+              number:
+                type: string
+              password:
+                type: string
+              pin:
+                type: string
+              sim-id:
+                type: string
+              sim-operator-id:
+                type: string
+              username:
+                type: string
 
       bonds:
         type: object
@@ -225,7 +221,76 @@ properties:
           # network.bonds.<interface>
           ".*$":
             additionalProperties: false
-            properties: {}
+            properties:
+              interfaces:
+                type: array
+                uniqueItems: true
+                items:
+                  type: string
+              parameters:
+                type: object
+                additionalProperties: false
+                properties:
+                  mode:
+                    type: string
+                    enum: [balance-rr, active-backup, balance-xor, broadcast, 802.3ad, balance-tlb, balance-alb]
+                  lacp-rate:
+                    type: string
+                    enum: [slow, fast]
+                  mii-monitor-interval:
+                    $ref: /schemas/interval
+                  min-links:
+                    type: integer
+                    minimum: 0
+                  transmit-hash-policy:
+                    type: string
+                    enum: [layer2, layer3+4, layer2+3, encap2+3, encap3+4]
+                  ad-select:
+                    type: string
+                    enum: [stable, bandwidth, count]
+                  all-members-active:
+                    type: boolean
+                  arp-interval:
+                    $ref: /schemas/interval
+                  arp-ip-targets:
+                    type: array
+                    uniqueItems: true
+                    maxItems: 16
+                    items:
+                      type: string
+                      format: ipv4
+                  arp-validate:
+                    type: string
+                    enum: [none, active, backup, all]
+                  arp-all-targets:
+                    type: string
+                    enum: [any, all]
+                  up-delay:
+                    $ref: /schemas/interval
+                  down-delay:
+                    $ref: /schemas/interval
+                  fail-over-mac-policy:
+                    type: string
+                    enum: [none, active, follow]
+                  gratuitous-arp:
+                    type: integer
+                    minimum: 1
+                    maximum: 255
+                  packets-per-member:
+                    type: integer
+                    minimum: 0
+                    maximum: 65535
+                  primary-reselect-policy:
+                    type: string
+                    enum: [always, better, failure]
+                  resend-igmp:
+                    type: integer
+                    minimum: 0
+                    maximum: 255
+                  learn-packet-interval:
+                    $ref: /schemas/interval
+                  primary:
+                    type: string
 
       tunnels:
         type: object
@@ -237,7 +302,176 @@ properties:
           # network.tunnels.<interface>
           ".*$":
             additionalProperties: false
-            properties: {}
+            required: [mode]
+            properties:
+              mode:
+                type: string
+                enum: [sit, gre, ip6gre, ipip, ipip6, ip6ip6, vti, vti6, gretap, ip6gretap, isatap, wireguard, vxlan]
+              local:
+                type: string
+              remote:
+                type: string
+              key:
+                $ref: /schemas/tunnel-keys
+              keys:
+                $ref: /schemas/tunnel-keys
+              ttl:
+                type: integer
+                minimum: 1
+                maximum: 255
+              # Listening port of a WireGuard tunnel, destination port of a VXLAN one.
+              port:
+                type: integer
+                minimum: 1
+                maximum: 65535
+
+              # VXLAN only
+              id:
+                type: integer
+                minimum: 1
+                maximum: 16777215
+              link:
+                type: string
+              mac-learning:
+                type: boolean
+              ageing:
+                $ref: /schemas/interval
+              limit:
+                type: integer
+                minimum: 0
+              arp-proxy:
+                type: boolean
+              notifications:
+                type: array
+                uniqueItems: true
+                items:
+                  type: string
+                  enum: [l2-miss, l3-miss]
+              short-circuit:
+                type: boolean
+              checksums:
+                type: array
+                uniqueItems: true
+                items:
+                  type: string
+                  enum: [udp, zero-udp6-tx, zero-udp6-rx, remote-tx, remote-rx]
+              extensions:
+                type: array
+                uniqueItems: true
+                items:
+                  type: string
+                  enum: [group-policy, generic-protocol]
+              port-range:
+                type: array
+                minItems: 2
+                maxItems: 2
+                items:
+                  type: integer
+                  minimum: 1
+                  maximum: 65535
+              flow-label:
+                type: integer
+                minimum: 0
+                maximum: 1048575
+              do-not-fragment:
+                type: boolean
+
+              # WireGuard only
+              mark:
+                type: integer
+                minimum: 0
+              peers:
+                type: array
+                items:
+                  type: object
+                  additionalProperties: false
+                  properties:
+                    # host:port or [IPv6 address]:port
+                    endpoint:
+                      type: string
+                      format: endpoint
+                    allowed-ips:
+                      type: array
+                      items:
+                        type: string
+                        format: cidr
+                    keepalive:
+                      type: integer
+                      minimum: 0
+                      maximum: 65535
+                    keys:
+                      type: object
+                      additionalProperties: false
+                      properties:
+                        public:
+                          $ref: /schemas/wireguard-key
+                        shared:
+                          $ref: /schemas/wireguard-key
+
+            # Settings that only make sense for some modes are rejected for the others, they
+            # are reported as unexpected keywords.
+            allOf:
+              - if:
+                  required: [mode]
+                  properties:
+                    mode:
+                      const: vxlan
+                else:
+                  properties:
+                    id: false
+                    link: false
+                    mac-learning: false
+                    ageing: false
+                    limit: false
+                    arp-proxy: false
+                    notifications: false
+                    short-circuit: false
+                    checksums: false
+                    extensions: false
+                    port-range: false
+                    flow-label: false
+                    do-not-fragment: false
+              - if:
+                  required: [mode]
+                  properties:
+                    mode:
+                      const: wireguard
+                then:
+                  properties:
+                    key:
+                      type: [string, object]
+                      format: wireguard-key
+                      properties:
+                        input: false
+                        output: false
+                        private:
+                          format: wireguard-key
+                    keys:
+                      type: [string, object]
+                      format: wireguard-key
+                      properties:
+                        input: false
+                        output: false
+                        private:
+                          format: wireguard-key
+                else:
+                  properties:
+                    mark: false
+                    peers: false
+                    key:
+                      properties:
+                        private: false
+                    keys:
+                      properties:
+                        private: false
+              - if:
+                  required: [mode]
+                  properties:
+                    mode:
+                      enum: [vxlan, wireguard]
+                else:
+                  properties:
+                    port: false
 
       vrfs:
         type: object
@@ -246,10 +480,21 @@ properties:
             $ref: /schemas/renderer
 
         patternProperties:
-          # network.tunnels.<interface>
+          # network.vrfs.<interface>
           ".*$":
             additionalProperties: false
-            properties: {}
+            required: [table]
+            properties:
+              # Routes and routing policy rules of the VRF must use the same table, they
+              # can also leave it out.
+              table:
+                type: integer
+                minimum: 0
+              interfaces:
+                type: array
+                uniqueItems: true
+                items:
+                  type: string
 
       wifis:
         type: object
@@ -331,6 +576,37 @@ $defs:
     "$schema": "http://json-schema.org/draft-07/schema#"
     type: string
     enum: [networkd, NetworkManager, sriov]
+
+  # A number, in milliseconds or seconds depending on the setting, or a number with a time
+  # unit, e.g. 100, 100ms or 2s.
+  interval:
+    $id: /schemas/interval
+    "$schema": "http://json-schema.org/draft-07/schema#"
+    type: [integer, string]
+    minimum: 0
+    pattern: ^[0-9]+(us|ms|s|min|m|h)?$
+
+  # The key of a tunnel, or its keys. GRE-like tunnels use an input and an output key, either
+  # a number or an IPv4 address, WireGuard tunnels use a private key.
+  tunnel-keys:
+    $id: /schemas/tunnel-keys
+    "$schema": "http://json-schema.org/draft-07/schema#"
+    type: [string, integer, object]
+    additionalProperties: false
+    properties:
+      input:
+        type: [string, integer]
+      output:
+        type: [string, integer]
+      private:
+        type: string
+
+  # 32 bytes encoded in base64, or the absolute path of a file holding the key.
+  wireguard-key:
+    $id: /schemas/wireguard-key
+    "$schema": "http://json-schema.org/draft-07/schema#"
+    type: string
+    format: wireguard-key
 "#;
 
 pub const COMMON_PROPERTIES: &str = r#"
@@ -417,25 +693,25 @@ dhcp6-overrides:
 accept-ra:
   type: boolean
 
+# Addresses are either a plain string or a map with a single address and its options.
+# Using a type list instead of anyOf lets errors inside the options be reported directly.
 addresses:
   type: array
   uniqueItems: true
   items:
-    anyOf:
-      - type: object
-        patternProperties:
-          ".*$": # TODO: regex to match ipv4 and ipv6 with prefix
-            type: object
-            additionalProperties: false
-            properties:
-              lifetime:
-                type: string
-                enum: [forever, 0]
-              label:
-                type: string
-                maxLength: 15
-      - type: string
-        pattern: .*$ # TODO: regex to match ipv4 and ipv6 with prefix
+    type: [object, string]
+    patternProperties:
+      ".*$": # TODO: regex to match ipv4 and ipv6 with prefix
+        type: object
+        additionalProperties: false
+        properties:
+          lifetime:
+            type: [string, integer]
+            enum: [forever, 0]
+          label:
+            type: string
+            maxLength: 15
+    pattern: .*$ # TODO: regex to match ipv4 and ipv6 with prefix
 
 ipv6-address-generation:
   type: string
@@ -522,23 +798,25 @@ routes:
         minimum: 0
 
 routing-policy:
-  type: object
-  additionalProperties: false
-  properties:
-    from:
-      type: string
-    to:
-      type: string
-    table:
-      type: integer
-      minimum: 0
-    priority:
-      type: integer
-    mark:
-      type: integer
-      minimum: 1
-    type-of-service:
-      type: integer
+  type: array
+  items:
+    type: object
+    additionalProperties: false
+    properties:
+      from:
+        type: string
+      to:
+        type: string
+      table:
+        type: integer
+        minimum: 0
+      priority:
+        type: integer
+      mark:
+        type: integer
+        minimum: 1
+      type-of-service:
+        type: integer
 
 neigh-suppress:
   type: boolean
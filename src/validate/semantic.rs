@@ -0,0 +1,103 @@
+use serde_json::value::Value;
+
+use super::error::{pointer_push, Error, ErrorKind};
+
+// Checks rules that depend on several nodes and can't be written in the schema. They
+// only look at nodes of the expected type, anything else is already reported by the
+// schema.
+pub fn check(data: &Value) -> Vec<Error> {
+    let mut errors = Vec::new();
+
+    if let Some(Value::Object(vrfs)) = data.pointer("/network/vrfs") {
+        for (name, vrf) in vrfs {
+            let pointer = pointer_push("/network/vrfs", name);
+            check_vrf_tables(&pointer, vrf, &mut errors);
+        }
+    }
+
+    errors
+}
+
+// Like netplan, only accept routes and routing policy rules in a VRF that leave the table
+// out or use the one of the VRF.
+fn check_vrf_tables(pointer: &str, vrf: &Value, errors: &mut Vec<Error>) {
+    let table = match vrf.get("table") {
+        Some(table) if table.is_u64() => table,
+        _ => return,
+    };
+
+    let mut tables = Vec::new();
+    if let Some(Value::Array(routes)) = vrf.get("routes") {
+        for (i, route) in routes.iter().enumerate() {
+            tables.push((format!("{pointer}/routes/{i}/table"), route.get("table")));
+        }
+    }
+    if let Some(Value::Array(rules)) = vrf.get("routing-policy") {
+        for (i, rule) in rules.iter().enumerate() {
+            tables.push((
+                format!("{pointer}/routing-policy/{i}/table"),
+                rule.get("table"),
+            ));
+        }
+    }
+
+    for (instance_path, value) in tables {
+        let value = match value {
+            Some(value) if value.is_u64() && value != table => value,
+            _ => continue,
+        };
+
+        errors.push(Error {
+            kind: ErrorKind::MismatchedValue {
+                expected: table.clone(),
+                reference: pointer_push(pointer, "table"),
+            },
+            instance_path,
+            instance: Some(value.clone()),
+            schema_path: String::new(),
+            span: None,
+            origins: Vec::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_vrf_tables() {
+        let data = json!({
+            "network": {
+                "vrfs": {
+                    "vrf0": {
+                        "table": 42,
+                        "routes": [
+                            {"to": "default", "via": "10.0.0.1"},
+                            {"to": "10.1.0.0/16", "via": "10.0.0.1", "table": 42},
+                            {"to": "10.2.0.0/16", "via": "10.0.0.1", "table": 100},
+                        ],
+                        "routing-policy": [
+                            {"from": "10.0.0.0/24", "table": 43},
+                            {"from": "10.0.1.0/24"},
+                        ],
+                    },
+                    // The schema reports the missing table.
+                    "vrf1": {
+                        "routes": [{"to": "default", "via": "10.0.0.1", "table": 100}],
+                    },
+                }
+            }
+        });
+
+        let messages: Vec<String> = check(&data).iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unexpected value /network/vrfs/vrf0/routes/2/table: 100, expected 42 as in /network/vrfs/vrf0/table",
+                "Unexpected value /network/vrfs/vrf0/routing-policy/0/table: 43, expected 42 as in /network/vrfs/vrf0/table",
+            ]
+        );
+    }
+}
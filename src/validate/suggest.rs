@@ -0,0 +1,42 @@
+use serde_json::value::Value;
+
+// How many suggestions are offered at most for a single keyword.
+const MAX_SUGGESTIONS: usize = 3;
+
+// Finds the properties allowed next to an unexpected keyword that are close enough to it
+// to be a likely typo, e.g. "lynk" -> "link" or "gateway-4" -> "gateway4".
+//
+// schema_path is the path of the failing additionalProperties keyword, its parent is the
+// schema object holding the list of valid properties.
+pub fn suggestions(schema: &Value, schema_path: &str, keyword: &str) -> Vec<String> {
+    let parent = match schema_path.strip_suffix("/additionalProperties") {
+        Some(parent) => parent,
+        None => return Vec::new(),
+    };
+
+    let properties = match schema
+        .pointer(parent)
+        .and_then(|s| s.get("properties"))
+        .and_then(Value::as_object)
+    {
+        Some(properties) => properties,
+        None => return Vec::new(),
+    };
+
+    // Allow roughly one edit for every three characters, at least one and at most three.
+    let threshold = (keyword.chars().count() / 3).clamp(1, 3);
+
+    let mut candidates: Vec<(usize, &String)> = properties
+        .keys()
+        .map(|property| (strsim::levenshtein(keyword, property), property))
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+
+    candidates.sort();
+
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, property)| property.clone())
+        .collect()
+}
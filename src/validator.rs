@@ -0,0 +1,117 @@
+use jsonschema::JSONSchema;
+use serde_json::value::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::validate::{self, schema, Error};
+
+/// Validates netplan configuration against the bundled schema. Compiling the schema is by far
+/// the most expensive step, so a `Validator` is meant to be built once and reused, e.g.
+/// through [`Validator::global`]. It can be shared between threads.
+pub struct Validator {
+    schema: JSONSchema,
+}
+
+// Fails to compile if Validator stops being shareable between threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Validator>();
+};
+
+impl Validator {
+    /// Builds a validator of its own, compiling the schema.
+    pub fn new() -> Validator {
+        Validator {
+            // The schema is bundled, failing to compile it is a bug.
+            schema: schema::build_schema().expect("the bundled schema should compile"),
+        }
+    }
+
+    /// A validator shared by the whole program, built the first time it's used.
+    pub fn global() -> &'static Validator {
+        static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+        VALIDATOR.get_or_init(Validator::new)
+    }
+
+    // Lets the tests of the validate module share the compiled schema.
+    #[cfg(test)]
+    pub(crate) fn schema(&self) -> &JSONSchema {
+        &self.schema
+    }
+
+    /// Validates a YAML document, errors point to their position in it.
+    pub fn validate_str(&self, yaml: &str) -> Result<(), Vec<Error>> {
+        validate::validate(&self.schema, yaml)
+    }
+
+    /// Validates an already parsed configuration, errors don't have a position.
+    pub fn validate_value(&self, data: &Value) -> Result<(), Vec<Error>> {
+        validate::validate_value(&self.schema, data)
+    }
+
+    /// Validates a YAML file. Failing to read it isn't a validation error, it's returned
+    /// as the outer error.
+    pub fn validate_file<P: AsRef<Path>>(&self, path: P) -> io::Result<Result<(), Vec<Error>>> {
+        let yaml = fs::read_to_string(path)?;
+        Ok(self.validate_str(&yaml))
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Validator::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validator() {
+        let validator = Validator::global();
+
+        assert!(validator
+            .validate_str("network:\n  version: 2\n  renderer: networkd\n")
+            .is_ok());
+        let errors = validator
+            .validate_str("network:\n  renderer: something\n")
+            .unwrap_err();
+        assert_eq!(errors[0].location().unwrap().line, 2);
+
+        assert!(validator
+            .validate_value(&json!({"network": {"version": 2}}))
+            .is_ok());
+        assert!(validator
+            .validate_value(&json!({"network": {"version": 3}}))
+            .is_err());
+
+        assert!(validator
+            .validate_file("tests/good_vlan.yaml")
+            .unwrap()
+            .is_ok());
+        assert!(validator
+            .validate_file("tests/bad_vlan.yaml")
+            .unwrap()
+            .is_err());
+        let err = validator.validate_file("tests/missing.yaml").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_global_validator() {
+        assert!(std::ptr::eq(Validator::global(), Validator::global()));
+
+        let results: Vec<bool> = std::thread::scope(|scope| {
+            let threads: Vec<_> = ["network: {version: 2}", "network: {version: 3}"]
+                .into_iter()
+                .map(|yaml| scope.spawn(move || Validator::global().validate_str(yaml).is_ok()))
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+        assert_eq!(results, vec![true, false]);
+    }
+}